@@ -0,0 +1,16 @@
+CREATE TABLE structure_downloads (
+    id BIGSERIAL PRIMARY KEY,
+    file BIGINT NOT NULL REFERENCES structure_files (id) ON DELETE CASCADE,
+    "user" BIGINT REFERENCES users (id) ON DELETE SET NULL,
+    ip TEXT,
+    created TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX structure_downloads_file_created_idx ON structure_downloads (file, created);
+
+CREATE TABLE structure_downloads_daily (
+    structure BIGINT NOT NULL REFERENCES structures (id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    downloads BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (structure, day)
+);
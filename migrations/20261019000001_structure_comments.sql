@@ -0,0 +1,15 @@
+ALTER TABLE users ADD COLUMN staff BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE structure_comments (
+    id BIGSERIAL PRIMARY KEY,
+    structure BIGINT NOT NULL REFERENCES structures (id) ON DELETE CASCADE,
+    parent BIGINT REFERENCES structure_comments (id) ON DELETE CASCADE,
+    author BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    body TEXT NOT NULL,
+    removed TEXT CHECK (removed IN ('author', 'moderator')),
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    edited TIMESTAMP
+);
+
+CREATE INDEX structure_comments_structure_idx ON structure_comments (structure, id) WHERE parent IS NULL;
+CREATE INDEX structure_comments_parent_idx ON structure_comments (parent, id);
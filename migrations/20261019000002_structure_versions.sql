@@ -0,0 +1,22 @@
+CREATE TABLE structure_versions (
+    id BIGSERIAL PRIMARY KEY,
+    structure BIGINT NOT NULL REFERENCES structures (id) ON DELETE CASCADE,
+    label TEXT NOT NULL,
+    changelog TEXT NOT NULL DEFAULT '',
+    description TEXT NOT NULL,
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (structure, label)
+);
+
+INSERT INTO structure_versions (structure, label, description, created)
+SELECT id, '1.0', description, created FROM structures;
+
+ALTER TABLE structures ADD COLUMN latest BIGINT REFERENCES structure_versions (id) ON DELETE SET NULL;
+
+UPDATE structures s SET latest = v.id FROM structure_versions v WHERE v.structure = s.id;
+
+ALTER TABLE structure_files ADD COLUMN version BIGINT REFERENCES structure_versions (id) ON DELETE CASCADE;
+
+UPDATE structure_files f SET version = s.latest FROM structures s WHERE s.id = f.structure;
+
+ALTER TABLE structure_files ALTER COLUMN version SET NOT NULL;
@@ -0,0 +1,9 @@
+ALTER TABLE structures
+    ADD COLUMN visibility TEXT NOT NULL DEFAULT 'draft'
+        CHECK (visibility IN ('draft', 'private', 'unlisted', 'public')),
+    ADD COLUMN published TIMESTAMP,
+    ADD COLUMN scheduled TIMESTAMP;
+
+UPDATE structures SET visibility = 'public', published = created;
+
+CREATE INDEX structures_scheduled_idx ON structures (scheduled) WHERE scheduled IS NOT NULL;
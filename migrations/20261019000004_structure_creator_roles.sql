@@ -0,0 +1,26 @@
+ALTER TABLE structure_creators
+    ADD COLUMN role TEXT NOT NULL DEFAULT 'contributor'
+        CHECK (role IN ('owner', 'maintainer', 'contributor')),
+    ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+
+UPDATE structure_creators c
+SET role = CASE WHEN r.position = 1 THEN 'owner' ELSE 'maintainer' END,
+    position = r.position
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY structure ORDER BY id) AS position
+    FROM structure_creators
+) r
+WHERE r.id = c.id;
+
+CREATE UNIQUE INDEX structure_creators_structure_creator_idx ON structure_creators (structure, creator);
+CREATE UNIQUE INDEX structure_creators_owner_idx ON structure_creators (structure) WHERE role = 'owner';
+
+CREATE TABLE structure_invitations (
+    id BIGSERIAL PRIMARY KEY,
+    structure BIGINT NOT NULL REFERENCES structures (id) ON DELETE CASCADE,
+    inviter BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    invitee BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    role TEXT NOT NULL CHECK (role IN ('maintainer', 'contributor')),
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (structure, invitee)
+);
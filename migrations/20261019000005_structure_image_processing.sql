@@ -0,0 +1,22 @@
+ALTER TABLE structure_images
+    ADD COLUMN width INTEGER,
+    ADD COLUMN height INTEGER,
+    ADD COLUMN blurhash TEXT;
+
+CREATE TABLE structure_image_variants (
+    image BIGINT NOT NULL REFERENCES structure_images (id) ON DELETE CASCADE,
+    size TEXT NOT NULL CHECK (size IN ('thumbnail', 'card', 'full')),
+    format TEXT NOT NULL CHECK (format IN ('webp', 'jpeg')),
+    url TEXT NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    PRIMARY KEY (image, size, format)
+);
+
+CREATE TABLE structure_image_uploads (
+    id BIGSERIAL PRIMARY KEY,
+    structure BIGINT NOT NULL REFERENCES structures (id) ON DELETE CASCADE,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    created TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,3 @@
+ALTER TABLE structure_files
+    ADD COLUMN format TEXT,
+    ADD COLUMN metadata JSONB;
@@ -0,0 +1,46 @@
+CREATE TABLE blobs (
+    hash TEXT PRIMARY KEY,
+    size BIGINT NOT NULL,
+    refs INTEGER NOT NULL DEFAULT 0,
+    -- Set while nothing references the blob; the collector removes it once this is old enough.
+    orphaned TIMESTAMP DEFAULT NOW(),
+    created TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX blobs_orphaned_idx ON blobs (orphaned) WHERE orphaned IS NOT NULL;
+
+ALTER TABLE structure_files
+    ADD COLUMN name TEXT,
+    ADD COLUMN blob TEXT REFERENCES blobs (hash);
+
+ALTER TABLE structure_images
+    ADD COLUMN blob TEXT REFERENCES blobs (hash);
+
+ALTER TABLE structure_image_variants
+    ADD COLUMN blob TEXT REFERENCES blobs (hash);
+
+CREATE FUNCTION count_blob_refs() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') AND OLD.blob IS NOT NULL THEN
+        UPDATE blobs
+        SET refs = refs - 1, orphaned = CASE WHEN refs - 1 = 0 THEN NOW() END
+        WHERE hash = OLD.blob;
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') AND NEW.blob IS NOT NULL THEN
+        UPDATE blobs SET refs = refs + 1, orphaned = NULL WHERE hash = NEW.blob;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER structure_files_blob_refs
+    AFTER INSERT OR DELETE OR UPDATE OF blob ON structure_files
+    FOR EACH ROW EXECUTE FUNCTION count_blob_refs();
+
+CREATE TRIGGER structure_images_blob_refs
+    AFTER INSERT OR DELETE OR UPDATE OF blob ON structure_images
+    FOR EACH ROW EXECUTE FUNCTION count_blob_refs();
+
+CREATE TRIGGER structure_image_variants_blob_refs
+    AFTER INSERT OR DELETE OR UPDATE OF blob ON structure_image_variants
+    FOR EACH ROW EXECUTE FUNCTION count_blob_refs();
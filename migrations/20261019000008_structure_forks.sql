@@ -0,0 +1,5 @@
+ALTER TABLE structures
+    ADD COLUMN parent BIGINT REFERENCES structures (id) ON DELETE SET NULL,
+    ADD COLUMN parent_note TEXT;
+
+CREATE INDEX structures_parent_idx ON structures (parent) WHERE parent IS NOT NULL;
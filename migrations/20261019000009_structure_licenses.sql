@@ -0,0 +1,8 @@
+ALTER TABLE structures
+    ADD COLUMN license TEXT,
+    ADD COLUMN license_text TEXT,
+    ADD COLUMN requires_attribution BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN allows_commercial_use BOOLEAN NOT NULL DEFAULT false,
+    ADD CONSTRAINT structures_license_text_check CHECK ((license = 'custom') = (license_text IS NOT NULL));
+
+CREATE INDEX structures_license_idx ON structures (license);
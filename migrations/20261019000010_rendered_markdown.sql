@@ -0,0 +1,4 @@
+-- Rendered on write; existing rows are filled in by the markdown backfill task on startup.
+ALTER TABLE structures ADD COLUMN description_html TEXT;
+ALTER TABLE structure_versions ADD COLUMN description_html TEXT;
+ALTER TABLE users ADD COLUMN bio_html TEXT;
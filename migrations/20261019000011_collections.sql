@@ -0,0 +1,23 @@
+CREATE TABLE collections (
+    id BIGSERIAL PRIMARY KEY,
+    owner BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    visibility TEXT NOT NULL DEFAULT 'private'
+        CHECK (visibility IN ('private', 'unlisted', 'public')),
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX collections_owner_idx ON collections (owner, updated);
+
+CREATE TABLE collection_items (
+    collection BIGINT NOT NULL REFERENCES collections (id) ON DELETE CASCADE,
+    structure BIGINT NOT NULL REFERENCES structures (id) ON DELETE CASCADE,
+    note TEXT,
+    position INTEGER NOT NULL,
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (collection, structure)
+);
+
+CREATE INDEX collection_items_structure_idx ON collection_items (structure);
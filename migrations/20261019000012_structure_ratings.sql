@@ -0,0 +1,40 @@
+CREATE TABLE structure_ratings (
+    structure BIGINT NOT NULL REFERENCES structures (id) ON DELETE CASCADE,
+    "user" BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    rating INTEGER NOT NULL CHECK (rating BETWEEN 1 AND 5),
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (structure, "user")
+);
+
+CREATE INDEX structure_ratings_user_idx ON structure_ratings ("user");
+
+-- The score pulls the mean towards 3 stars as if every structure had 5 extra neutral votes,
+-- so a handful of ratings can't outrank a well-rated structure with many.
+ALTER TABLE structures
+    ADD COLUMN ratings BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN rating_sum BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN rating_score DOUBLE PRECISION NOT NULL
+        GENERATED ALWAYS AS ((rating_sum + 15)::DOUBLE PRECISION / (ratings + 5)) STORED;
+
+CREATE INDEX structures_rating_score_idx ON structures (rating_score);
+
+CREATE FUNCTION count_structure_ratings() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        UPDATE structures
+        SET ratings = ratings - 1, rating_sum = rating_sum - OLD.rating
+        WHERE id = OLD.structure;
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        UPDATE structures
+        SET ratings = ratings + 1, rating_sum = rating_sum + NEW.rating
+        WHERE id = NEW.structure;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER structure_ratings_count
+    AFTER INSERT OR DELETE OR UPDATE OF rating ON structure_ratings
+    FOR EACH ROW EXECUTE FUNCTION count_structure_ratings();
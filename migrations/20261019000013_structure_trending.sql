@@ -0,0 +1,12 @@
+-- Rebuilt periodically from recent activity; see tasks::trending.
+CREATE TABLE structure_trending (
+    structure BIGINT NOT NULL REFERENCES structures (id) ON DELETE CASCADE,
+    period TEXT NOT NULL CHECK (period IN ('day', 'week', 'month')),
+    score DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (period, structure)
+);
+
+CREATE INDEX structure_trending_score_idx ON structure_trending (period, score DESC);
+
+CREATE INDEX structure_comments_structure_created_idx ON structure_comments (structure, created);
+CREATE INDEX structure_ratings_updated_idx ON structure_ratings (updated);
@@ -0,0 +1,28 @@
+CREATE UNIQUE INDEX users_slug_idx ON users (slug);
+
+-- Last time the name or slug changed, for rate limiting renames.
+ALTER TABLE users ADD COLUMN renamed TIMESTAMP;
+
+-- Names a user went by before, so that old links keep resolving.
+CREATE TABLE user_names (
+    name TEXT PRIMARY KEY,
+    "user" BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX user_names_user_idx ON user_names ("user");
+
+CREATE TABLE user_avatar_variants (
+    "user" BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    size TEXT NOT NULL CHECK (size IN ('small', 'medium', 'large')),
+    format TEXT NOT NULL CHECK (format IN ('webp', 'jpeg')),
+    url TEXT NOT NULL,
+    blob TEXT REFERENCES blobs (hash),
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    PRIMARY KEY ("user", size, format)
+);
+
+CREATE TRIGGER user_avatar_variants_blob_refs
+    AFTER INSERT OR DELETE OR UPDATE OF blob ON user_avatar_variants
+    FOR EACH ROW EXECUTE FUNCTION count_blob_refs();
@@ -0,0 +1,10 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Accounts from before this column existed all get the migration time as their join date.
+ALTER TABLE users
+    ADD COLUMN created TIMESTAMP NOT NULL DEFAULT NOW(),
+    ADD COLUMN private BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX users_created_idx ON users (created);
+CREATE INDEX users_name_trgm_idx ON users USING GIN (name gin_trgm_ops);
+CREATE INDEX users_slug_trgm_idx ON users USING GIN (slug gin_trgm_ops);
@@ -0,0 +1,12 @@
+CREATE TABLE user_follows (
+    follower BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    followee BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (follower, followee),
+    CHECK (follower <> followee)
+);
+
+CREATE INDEX user_follows_followee_idx ON user_follows (followee, created);
+
+-- The feed walks from followed creators to their structures.
+CREATE INDEX structure_creators_creator_idx ON structure_creators (creator, structure);
@@ -0,0 +1,22 @@
+CREATE TABLE notifications (
+    id BIGSERIAL PRIMARY KEY,
+    "user" BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    kind TEXT NOT NULL
+        CHECK (kind IN ('comment', 'reply', 'rating', 'invitation', 'follow', 'moderation')),
+    actor BIGINT REFERENCES users (id) ON DELETE SET NULL,
+    structure BIGINT REFERENCES structures (id) ON DELETE CASCADE,
+    comment BIGINT REFERENCES structure_comments (id) ON DELETE CASCADE,
+    read TIMESTAMP,
+    created TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX notifications_user_idx ON notifications ("user", (read IS NOT NULL), id DESC);
+
+-- Missing rows mean the kind is enabled on that channel.
+CREATE TABLE notification_preferences (
+    "user" BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    PRIMARY KEY ("user", kind, channel)
+);
@@ -0,0 +1,24 @@
+-- Wakes the notification streams of every server instance for users whose notifications changed.
+-- Payloads are only delivered once the transaction commits.
+CREATE FUNCTION signal_notifications() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('notifications', c."user"::TEXT)
+    FROM (SELECT DISTINCT "user" FROM changed) c;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER notifications_inserted
+    AFTER INSERT ON notifications
+    REFERENCING NEW TABLE AS changed
+    FOR EACH STATEMENT EXECUTE FUNCTION signal_notifications();
+
+CREATE TRIGGER notifications_updated
+    AFTER UPDATE ON notifications
+    REFERENCING NEW TABLE AS changed
+    FOR EACH STATEMENT EXECUTE FUNCTION signal_notifications();
+
+CREATE TRIGGER notifications_deleted
+    AFTER DELETE ON notifications
+    REFERENCING OLD TABLE AS changed
+    FOR EACH STATEMENT EXECUTE FUNCTION signal_notifications();
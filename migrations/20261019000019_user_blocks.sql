@@ -0,0 +1,23 @@
+CREATE TABLE user_blocks (
+    blocker BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    blocked BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (blocker, blocked),
+    CHECK (blocker <> blocked)
+);
+
+CREATE INDEX user_blocks_blocked_idx ON user_blocks (blocked);
+
+CREATE TABLE user_mutes (
+    muter BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    muted BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (muter, muted),
+    CHECK (muter <> muted)
+);
+
+-- Listings filter on this so every query hides muted users the same way. Anonymous viewers
+-- have muted no one.
+CREATE FUNCTION is_muted(viewer BIGINT, author BIGINT) RETURNS BOOLEAN AS $$
+    SELECT EXISTS(SELECT 1 FROM user_mutes WHERE muter = viewer AND muted = author)
+$$ LANGUAGE sql STABLE;
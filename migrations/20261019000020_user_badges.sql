@@ -0,0 +1,7 @@
+CREATE TABLE user_badges (
+    "user" BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    badge TEXT NOT NULL
+        CHECK (badge IN ('first_upload', 'thousand_downloads', 'ten_thousand_downloads', 'hundred_followers', 'staff')),
+    awarded TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY ("user", badge)
+);
@@ -0,0 +1,5 @@
+-- `private` already kept users out of the directory; it now also reduces their profile to a name.
+ALTER TABLE users
+    ADD COLUMN hide_activity BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN hide_collections BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN hide_followers BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,2 @@
+-- Rendered on write; existing comments are filled in by the markdown backfill task on startup.
+ALTER TABLE structure_comments ADD COLUMN body_html TEXT;
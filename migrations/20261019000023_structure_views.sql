@@ -0,0 +1,16 @@
+CREATE TABLE structure_views (
+    structure BIGINT NOT NULL REFERENCES structures (id) ON DELETE CASCADE,
+    -- Either `user:<id>` or `ip:<address>`, so each viewer counts once a day.
+    viewer TEXT NOT NULL,
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (structure, viewer)
+);
+
+CREATE INDEX structure_views_created_idx ON structure_views (created);
+
+CREATE TABLE structure_views_daily (
+    structure BIGINT NOT NULL REFERENCES structures (id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    views BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (structure, day)
+);
@@ -0,0 +1,116 @@
+use std::path::{Path, PathBuf};
+use sqlx::PgPool;
+use destru::{decode_sqids, encode_sqids};
+use crate::archives::{export, import};
+use crate::models::ids::STRUCTURE_FLAG;
+
+const USAGE: &str = "usage: destru export <structure> <archive> | destru import <archive or directory>...";
+
+/// Runs an archive subcommand, returning whether it fully succeeded.
+///
+/// `export` writes one structure to a ZIP. `import` takes archives or directories of them,
+/// importing each in its own transaction so one bad archive doesn't stop the batch.
+pub async fn run(args: &[String], postgre: &PgPool) -> bool {
+    match args {
+        [command, structure, archive] if command == "export" => export_to(structure, Path::new(archive), postgre).await,
+        [command, paths @ ..] if command == "import" && !paths.is_empty() => import_all(paths, postgre).await,
+        _ => {
+            eprintln!("{}", USAGE);
+            false
+        }
+    }
+}
+
+async fn export_to(structure: &str, archive: &Path, postgre: &PgPool) -> bool {
+    let id = match decode_sqids(STRUCTURE_FLAG, structure) {
+        Ok(id) => id,
+        Err(_) => {
+            log::error!("invalid structure id {}", structure);
+            return false;
+        }
+    };
+
+    let mut conn = postgre.acquire().await.expect("failed to acquire connection");
+
+    let data = match export(id, &mut conn).await {
+        Ok(data) => data,
+        Err(e) => {
+            log::error!("failed to export structure {}: {}", structure, e);
+            return false;
+        }
+    };
+
+    match std::fs::write(archive, data) {
+        Ok(()) => {
+            log::info!("exported structure {} to {}", structure, archive.display());
+            true
+        }
+        Err(e) => {
+            log::error!("failed to write {}: {}", archive.display(), e);
+            false
+        }
+    }
+}
+
+async fn import_all(paths: &[String], postgre: &PgPool) -> bool {
+    let mut archives = Vec::new();
+    for path in paths.iter().map(PathBuf::from) {
+        if path.is_dir() {
+            let entries = match std::fs::read_dir(&path) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    log::error!("failed to read {}: {}", path.display(), e);
+                    return false;
+                }
+            };
+
+            let mut found: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("zip")))
+                .collect();
+            found.sort();
+            archives.extend(found);
+        } else {
+            archives.push(path);
+        }
+    }
+
+    let mut failed = 0;
+    for archive in &archives {
+        let data = match std::fs::read(archive) {
+            Ok(data) => data,
+            Err(e) => {
+                log::error!("failed to read {}: {}", archive.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        let mut tx = postgre.begin().await.expect("failed to begin transaction");
+
+        match import(data, None, &mut tx).await {
+            Ok(imported) => {
+                tx.commit().await.expect("failed to commit transaction");
+
+                log::info!(
+                    "imported {} as {}",
+                    archive.display(),
+                    encode_sqids(STRUCTURE_FLAG, imported.structure.0).unwrap_or_default()
+                );
+                if !imported.unmatched.is_empty() {
+                    log::warn!("unmatched creators in {}: {}", archive.display(), imported.unmatched.join(", "));
+                }
+            }
+            Err(e) => {
+                tx.rollback().await.expect("failed to roll back transaction");
+
+                log::error!("failed to import {}: {}", archive.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    log::info!("imported {} of {} archives", archives.len() - failed, archives.len());
+
+    failed == 0
+}
@@ -0,0 +1,438 @@
+pub mod cli;
+
+use std::io::{self, Cursor, Read, Write};
+use serde_json::Value;
+use sqlx::PgConnection;
+use thiserror::Error;
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+use crate::formats::{FileMetadata, REGISTRY};
+use crate::models::archives::{Imported, Manifest, ManifestCreator, ManifestFile, ManifestImage, ManifestStructure, MANIFEST_VERSION};
+use crate::models::creators::CreatorRole;
+use crate::models::ids::StructureID;
+use crate::models::structures::{is_valid_license, License, Visibility};
+use crate::routes::files::FILE_NAME_REGEX;
+use crate::routes::versions::{insert_version, is_valid_label};
+use crate::storage::blobs::{self, BlobError};
+use crate::storage::local;
+use crate::tasks::images::upload_key;
+
+const MANIFEST_PATH: &str = "manifest.json";
+const LICENSE_PATH: &str = "LICENSE.txt";
+
+/// Total decompressed size read out of an archive, guarding against zip bombs.
+const MAX_UNPACKED_SIZE: u64 = 512 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("结构不存在")]
+    NotFound,
+    #[error("清单无效")]
+    InvalidManifest,
+    #[error("不支持的清单版本: {0}")]
+    UnsupportedVersion(i64),
+    #[error("缺少文件: {0}")]
+    MissingEntry(String),
+    #[error("校验和不匹配: {0}")]
+    ChecksumMismatch(String),
+    #[error("文件无效: {0}")]
+    InvalidFile(String),
+    #[error("没有可匹配的创作者")]
+    NoCreators,
+    #[error("压缩包错误: {0}")]
+    Zip(#[from] ZipError),
+    #[error("存储错误: {0}")]
+    Storage(#[from] io::Error),
+    #[error("文件错误: {0}")]
+    Blob(#[from] BlobError),
+    #[error("数据库错误: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+struct Unpacked {
+    manifest: Manifest,
+    files: Vec<(Vec<u8>, &'static str, FileMetadata)>,
+    images: Vec<Vec<u8>>,
+}
+
+/// Packages a structure's latest version, creators and images as a ZIP with a JSON manifest.
+pub async fn export(structure: i64, conn: &mut PgConnection) -> Result<Vec<u8>, ArchiveError> {
+    let record = sqlx::query!(
+        r#"
+        SELECT s.name, s.summary, s.description, s.visibility, s.published, s.created,
+            s.license, s.license_text, s.requires_attribution, s.allows_commercial_use,
+            v.label AS "label?", v.changelog AS "changelog?"
+        FROM structures s
+        LEFT JOIN structure_versions v ON v.id = s.latest
+        WHERE s.id = $1
+        "#,
+        structure
+    )
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or(ArchiveError::NotFound)?;
+
+    let creators = sqlx::query!(
+        r"
+        SELECT u.name, c.role
+        FROM structure_creators c
+        JOIN users u ON u.id = c.creator
+        WHERE c.structure = $1
+        ORDER BY c.position, c.id
+        ",
+        structure
+    )
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|c| ManifestCreator {
+            name: c.name,
+            role: CreatorRole::parse(&c.role),
+        })
+        .collect();
+
+    let files = sqlx::query!(
+        r"
+        SELECT f.name, f.url, f.format, f.metadata
+        FROM structure_files f
+        JOIN structures s ON s.latest = f.version
+        WHERE s.id = $1
+        ORDER BY f.id
+        ",
+        structure
+    )
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let images = sqlx::query_scalar!(
+        r"SELECT url FROM structure_images WHERE structure = $1 ORDER BY id",
+        structure
+    )
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    // Structure files and images are already compressed.
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    let mut skipped = Vec::new();
+    let mut manifest_files = Vec::with_capacity(files.len());
+    for (i, file) in files.into_iter().enumerate() {
+        let data = match read_url(&file.url).await? {
+            Some(data) => data,
+            None => {
+                skipped.push(file.url);
+                continue;
+            }
+        };
+        let name = file.name.unwrap_or_else(|| file.url.rsplit('/').next().unwrap_or_default().to_owned());
+        let path = format!("files/{}/{}", i, name);
+
+        writer.start_file(path.as_str(), stored)?;
+        writer.write_all(&data)?;
+
+        manifest_files.push(ManifestFile {
+            name,
+            path,
+            checksum: blobs::hash(&data),
+            format: file.format,
+            metadata: file.metadata,
+        });
+    }
+
+    let mut manifest_images = Vec::with_capacity(images.len());
+    for (i, url) in images.into_iter().enumerate() {
+        let data = match read_url(&url).await? {
+            Some(data) => data,
+            None => {
+                skipped.push(url);
+                continue;
+            }
+        };
+        let path = format!("images/{}", i);
+
+        writer.start_file(path.as_str(), stored)?;
+        writer.write_all(&data)?;
+
+        manifest_images.push(ManifestImage {
+            path,
+            checksum: blobs::hash(&data),
+        });
+    }
+
+    let license = record.license.map(|license| License {
+        id: license,
+        text: record.license_text,
+        requires_attribution: record.requires_attribution,
+        allows_commercial_use: record.allows_commercial_use,
+    });
+
+    // The license travels as a readable file too, for anyone unpacking the archive by hand.
+    if let Some(license) = &license {
+        writer.start_file(LICENSE_PATH, SimpleFileOptions::default())?;
+        writer.write_all(license_file(license).as_bytes())?;
+    }
+
+    let manifest = Manifest {
+        version: MANIFEST_VERSION,
+        structure: ManifestStructure {
+            name: record.name,
+            summary: record.summary,
+            description: record.description,
+            version: record.label.unwrap_or_else(|| "1.0".to_owned()),
+            changelog: record.changelog.unwrap_or_default(),
+            visibility: Visibility::parse(&record.visibility),
+            published: record.published,
+            license,
+            created: record.created,
+        },
+        creators,
+        files: manifest_files,
+        images: manifest_images,
+        skipped,
+    };
+
+    writer.start_file(MANIFEST_PATH, SimpleFileOptions::default())?;
+    writer.write_all(&serde_json::to_vec_pretty(&manifest).unwrap())?;
+
+    Ok(writer.finish()?.into_inner())
+}
+
+/// Recreates a structure from an exported archive.
+///
+/// Creators are remapped by user name and unknown ones are skipped. If the owner can't be
+/// matched, the first matched creator takes ownership, falling back to `importer`.
+/// Everything is validated before the first write. Image sources are written to storage as they are
+/// queued, so if the transaction rolls back they stay on disk until the image task sweeps them.
+pub async fn import(data: Vec<u8>, importer: Option<i64>, conn: &mut PgConnection) -> Result<Imported, ArchiveError> {
+    let unpacked = actix_rt::task::spawn_blocking(move || unpack(&data))
+        .await
+        .map_err(io::Error::other)??;
+    let manifest = unpacked.manifest;
+
+    let mut creators: Vec<(i64, CreatorRole)> = Vec::with_capacity(manifest.creators.len());
+    let mut unmatched = Vec::new();
+    for creator in &manifest.creators {
+        let user = sqlx::query_scalar!(
+            r"SELECT id FROM users WHERE name = $1",
+            creator.name
+        )
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        match user {
+            Some(user) if !creators.iter().any(|(id, _)| *id == user) => creators.push((user, creator.role)),
+            Some(_) => {}
+            None => unmatched.push(creator.name.clone()),
+        }
+    }
+
+    // Exactly one owner, preferring the one named in the manifest.
+    let owner = match creators.iter().position(|(_, role)| *role == CreatorRole::Owner) {
+        Some(owner) => Some(owner),
+        None if !creators.is_empty() => Some(0),
+        None => None,
+    };
+    match (owner, importer) {
+        (Some(owner), _) => {
+            for (i, (_, role)) in creators.iter_mut().enumerate() {
+                if i == owner {
+                    *role = CreatorRole::Owner;
+                } else if *role == CreatorRole::Owner {
+                    *role = CreatorRole::Maintainer;
+                }
+            }
+        }
+        (None, Some(importer)) => creators.push((importer, CreatorRole::Owner)),
+        (None, None) => return Err(ArchiveError::NoCreators),
+    }
+
+    let license = manifest.structure.license.as_ref();
+
+    let structure = sqlx::query_scalar!(
+        r"
+        INSERT INTO structures (
+            name, summary, description, visibility, published,
+            license, license_text, requires_attribution, allows_commercial_use, created
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        RETURNING id
+        ",
+        manifest.structure.name,
+        manifest.structure.summary,
+        manifest.structure.description,
+        manifest.structure.visibility.as_str(),
+        manifest.structure.published,
+        license.map(|l| l.id.as_str()),
+        license.and_then(|l| l.text.as_deref()),
+        license.is_some_and(|l| l.requires_attribution),
+        license.is_some_and(|l| l.allows_commercial_use),
+        manifest.structure.created
+    )
+        .fetch_one(&mut *conn)
+        .await?;
+
+    for (i, (user, role)) in creators.iter().enumerate() {
+        sqlx::query!(
+            r"INSERT INTO structure_creators (structure, creator, role, position) VALUES ($1, $2, $3, $4)",
+            structure,
+            user,
+            role.as_str(),
+            i as i32 + 1
+        )
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    let version = insert_version(
+        structure,
+        &manifest.structure.version,
+        &manifest.structure.changelog,
+        Some(manifest.structure.description.clone()),
+        None,
+        &mut *conn,
+    )
+        .await
+        .expect("a new structure has no versions");
+
+    for (file, (data, format, metadata)) in manifest.files.iter().zip(unpacked.files) {
+        let blob = blobs::store(data, &mut *conn).await?;
+
+        sqlx::query!(
+            r"
+            INSERT INTO structure_files (structure, version, name, url, blob, format, metadata, created)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+            ",
+            structure,
+            version,
+            file.name,
+            blob.url,
+            blob.hash,
+            format,
+            serde_json::to_value(metadata).unwrap()
+        )
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    // Images go through the regular processing queue to get their variants.
+    for data in unpacked.images {
+        let upload = sqlx::query_scalar!(
+            r"INSERT INTO structure_image_uploads (structure) VALUES ($1) RETURNING id",
+            structure
+        )
+            .fetch_one(&mut *conn)
+            .await?;
+
+        local::write(&upload_key(upload), data).await?;
+    }
+
+    Ok(Imported {
+        structure: StructureID::from(structure),
+        unmatched,
+    })
+}
+
+fn license_file(license: &License) -> String {
+    let text = match &license.text {
+        Some(text) => text.clone(),
+        None => format!("SPDX-License-Identifier: {}", license.id),
+    };
+    let flag = |value| if value { "yes" } else { "no" };
+
+    format!(
+        "{}\n\nRequires attribution: {}\nAllows commercial use: {}\n",
+        text,
+        flag(license.requires_attribution),
+        flag(license.allows_commercial_use)
+    )
+}
+
+/// Reads an object from local storage, or nothing if the URL points elsewhere.
+async fn read_url(url: &str) -> Result<Option<Vec<u8>>, ArchiveError> {
+    match local::key(url) {
+        Some(key) => Ok(Some(local::read(key).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Reads and validates everything in the archive up front.
+fn unpack(data: &[u8]) -> Result<Unpacked, ArchiveError> {
+    let mut archive = ZipArchive::new(Cursor::new(data))?;
+    let mut budget = MAX_UNPACKED_SIZE;
+
+    let manifest: Value = serde_json::from_slice(&read_entry(&mut archive, MANIFEST_PATH, &mut budget)?)
+        .map_err(|_| ArchiveError::InvalidManifest)?;
+
+    // Checked before the full parse, so a newer layout is reported as such rather than as invalid.
+    match manifest.get("version").and_then(Value::as_i64) {
+        Some(version) if version == MANIFEST_VERSION as i64 => {}
+        Some(version) => return Err(ArchiveError::UnsupportedVersion(version)),
+        None => return Err(ArchiveError::InvalidManifest),
+    }
+
+    let manifest: Manifest = serde_json::from_value(manifest).map_err(|_| ArchiveError::InvalidManifest)?;
+
+    if !is_valid_label(&manifest.structure.version) {
+        return Err(ArchiveError::InvalidManifest);
+    }
+
+    let license = manifest.structure.license.as_ref();
+    if license.is_some_and(|l| !is_valid_license(Some(&l.id), l.text.as_deref())) {
+        return Err(ArchiveError::InvalidManifest);
+    }
+
+    let mut files = Vec::with_capacity(manifest.files.len());
+    for file in &manifest.files {
+        if !FILE_NAME_REGEX.is_match(&file.name) {
+            return Err(ArchiveError::InvalidFile(file.path.clone()));
+        }
+
+        let data = read_entry(&mut archive, &file.path, &mut budget)?;
+        if blobs::hash(&data) != file.checksum {
+            return Err(ArchiveError::ChecksumMismatch(file.path.clone()));
+        }
+
+        // Metadata is extracted again rather than trusted from the manifest.
+        let (format, metadata) = REGISTRY
+            .parse(&file.name, &data)
+            .map_err(|_| ArchiveError::InvalidFile(file.path.clone()))?;
+        files.push((data, format, metadata));
+    }
+
+    let mut images = Vec::with_capacity(manifest.images.len());
+    for image in &manifest.images {
+        let data = read_entry(&mut archive, &image.path, &mut budget)?;
+        if blobs::hash(&data) != image.checksum {
+            return Err(ArchiveError::ChecksumMismatch(image.path.clone()));
+        }
+        if image::guess_format(&data).is_err() {
+            return Err(ArchiveError::InvalidFile(image.path.clone()));
+        }
+        images.push(data);
+    }
+
+    Ok(Unpacked {
+        manifest,
+        files,
+        images,
+    })
+}
+
+fn read_entry(archive: &mut ZipArchive<Cursor<&[u8]>>, path: &str, budget: &mut u64) -> Result<Vec<u8>, ArchiveError> {
+    let entry = match archive.by_name(path) {
+        Ok(entry) => entry,
+        Err(ZipError::FileNotFound) => return Err(ArchiveError::MissingEntry(path.to_owned())),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut data = Vec::new();
+    entry.take(*budget + 1).read_to_end(&mut data)?;
+    if data.len() as u64 > *budget {
+        return Err(ArchiveError::InvalidFile(path.to_owned()));
+    }
+    *budget -= data.len() as u64;
+
+    Ok(data)
+}
@@ -0,0 +1,45 @@
+use crate::formats::nbt::{self, Tag};
+use crate::formats::{FileMetadata, FormatError, Parser};
+
+/// Litematica schematics.
+pub struct LitematicParser;
+
+impl Parser for LitematicParser {
+    fn format(&self) -> &'static str {
+        "litematic"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["litematic"]
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        nbt::is_nbt(data)
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<FileMetadata, FormatError> {
+        let root = nbt::read(data)?;
+
+        let metadata = root.get("Metadata").ok_or(FormatError::Mismatch)?;
+        let regions = root.get("Regions").and_then(Tag::as_compound).ok_or(FormatError::Mismatch)?;
+
+        let size = metadata.get("EnclosingSize").ok_or(FormatError::Mismatch)?;
+        let dimension = |key: &str| size.get(key).and_then(Tag::as_i64).ok_or(FormatError::Mismatch);
+
+        let entities = regions
+            .values()
+            .filter_map(|r| r.get("Entities").and_then(Tag::as_list))
+            .map(|e| e.len() as i64)
+            .sum();
+
+        Ok(FileMetadata {
+            version: root.get("Version").and_then(Tag::as_i64),
+            data_version: root.get("MinecraftDataVersion").and_then(Tag::as_i64),
+            width: dimension("x")?,
+            height: dimension("y")?,
+            length: dimension("z")?,
+            blocks: metadata.get("TotalBlocks").and_then(Tag::as_i64).unwrap_or_default(),
+            entities,
+        })
+    }
+}
@@ -0,0 +1,88 @@
+pub mod nbt;
+pub mod structure;
+pub mod sponge;
+pub mod litematic;
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+lazy_static! {
+    pub static ref REGISTRY: Registry = {
+        let mut registry = Registry::default();
+        registry.register(Box::new(structure::StructureParser));
+        registry.register(Box::new(sponge::SpongeParser));
+        registry.register(Box::new(litematic::LitematicParser));
+        registry
+    };
+}
+
+#[derive(Debug, Error)]
+pub enum FormatError {
+    #[error("不支持的格式")]
+    Unsupported,
+    #[error("文件内容与格式不符")]
+    Mismatch,
+    #[error("文件损坏: {0}")]
+    Corrupt(String),
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct FileMetadata {
+    pub version: Option<i64>,
+    pub data_version: Option<i64>,
+    pub width: i64,
+    pub height: i64,
+    pub length: i64,
+    pub blocks: i64,
+    pub entities: i64,
+}
+
+pub trait Parser: Send + Sync {
+    /// Name stored alongside the extracted metadata.
+    fn format(&self) -> &'static str;
+
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// Cheap check on the leading bytes, run before a full parse.
+    fn sniff(&self, data: &[u8]) -> bool;
+
+    fn parse(&self, data: &[u8]) -> Result<FileMetadata, FormatError>;
+}
+
+#[derive(Default)]
+pub struct Registry {
+    parsers: Vec<Box<dyn Parser>>,
+}
+
+impl Registry {
+    pub fn register(&mut self, parser: Box<dyn Parser>) {
+        self.parsers.push(parser);
+    }
+
+    /// Validates a file and extracts its metadata, picking the parser by extension and
+    /// falling back to the first parser that accepts the content.
+    pub fn parse(&self, name: &str, data: &[u8]) -> Result<(&'static str, FileMetadata), FormatError> {
+        let extension = name
+            .rsplit_once('.')
+            .map(|(_, e)| e.to_ascii_lowercase())
+            .unwrap_or_default();
+
+        if let Some(parser) = self.parsers.iter().find(|p| p.extensions().contains(&extension.as_str())) {
+            if !parser.sniff(data) {
+                return Err(FormatError::Mismatch);
+            }
+            return parser.parse(data).map(|m| (parser.format(), m));
+        }
+
+        self.parsers
+            .iter()
+            .filter(|p| p.sniff(data))
+            .find_map(|p| p.parse(data).ok().map(|m| (p.format(), m)))
+            .ok_or(FormatError::Unsupported)
+    }
+}
+
+pub fn is_air(name: &str) -> bool {
+    matches!(name, "minecraft:air" | "minecraft:cave_air" | "minecraft:void_air")
+}
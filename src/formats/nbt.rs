@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::mem;
+use flate2::read::GzDecoder;
+use crate::formats::FormatError;
+
+/// Decompressed NBT larger than this is rejected, guarding against gzip bombs.
+const MAX_DECOMPRESSED_SIZE: u64 = 256 * 1024 * 1024;
+const MAX_DEPTH: usize = 512;
+
+pub const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    ByteArray(Vec<u8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(HashMap<String, Tag>),
+    /// Floating point and int/long array payloads, which no parser needs, are skipped.
+    Other,
+}
+
+impl Tag {
+    pub fn get(&self, key: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Tag::Byte(v) => Some(v as i64),
+            Tag::Short(v) => Some(v as i64),
+            Tag::Int(v) => Some(v as i64),
+            Tag::Long(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&HashMap<String, Tag>> {
+        match self {
+            Tag::Compound(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Tag::ByteArray(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+}
+
+/// Whether the data looks like NBT, either gzipped or a raw root compound.
+pub fn is_nbt(data: &[u8]) -> bool {
+    data.starts_with(&GZIP_MAGIC) || data.first() == Some(&10)
+}
+
+/// Reads the root compound of a gzipped or uncompressed NBT document.
+pub fn read(data: &[u8]) -> Result<Tag, FormatError> {
+    let decompressed;
+    let data = if data.starts_with(&GZIP_MAGIC) {
+        let mut buf = Vec::new();
+        GzDecoder::new(data)
+            .take(MAX_DECOMPRESSED_SIZE + 1)
+            .read_to_end(&mut buf)
+            .map_err(|e| FormatError::Corrupt(e.to_string()))?;
+        if buf.len() as u64 > MAX_DECOMPRESSED_SIZE {
+            return Err(FormatError::Corrupt("decompressed data too large".to_owned()));
+        }
+        decompressed = buf;
+        &decompressed[..]
+    } else {
+        data
+    };
+
+    let mut reader = Reader {
+        data,
+        pos: 0,
+        budget: MAX_DECOMPRESSED_SIZE as usize,
+    };
+    if reader.u8()? != 10 {
+        return Err(FormatError::Corrupt("root tag is not a compound".to_owned()));
+    }
+    reader.string()?;
+    reader.payload(10, 0)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    /// Bytes left for the decoded tree, which can be far larger than its encoding.
+    budget: usize,
+}
+
+/// Smallest encoding of a payload, so list lengths can be checked before reading any element.
+fn min_size(id: u8) -> Option<usize> {
+    match id {
+        1 | 10 => Some(1),
+        2 | 8 => Some(2),
+        3 | 5 | 7 | 11 | 12 => Some(4),
+        4 | 6 => Some(8),
+        9 => Some(5),
+        _ => None,
+    }
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], FormatError> {
+        let end = self.pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| FormatError::Corrupt("unexpected end of data".to_owned()))?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, FormatError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i16(&mut self) -> Result<i16, FormatError> {
+        Ok(i16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32, FormatError> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i64(&mut self) -> Result<i64, FormatError> {
+        Ok(i64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn len(&mut self, size: usize) -> Result<usize, FormatError> {
+        let len = self.i32()?;
+        if len < 0 {
+            return Err(FormatError::Corrupt("negative length".to_owned()));
+        }
+        let len = len as usize;
+        // Reject lengths that can't possibly fit before allocating for them.
+        if len.saturating_mul(size) > self.data.len() - self.pos {
+            return Err(FormatError::Corrupt("length exceeds data".to_owned()));
+        }
+        Ok(len)
+    }
+
+    fn charge(&mut self, size: usize) -> Result<(), FormatError> {
+        self.budget = self.budget
+            .checked_sub(size)
+            .ok_or_else(|| FormatError::Corrupt("decoded data too large".to_owned()))?;
+        Ok(())
+    }
+
+    fn string(&mut self) -> Result<String, FormatError> {
+        let len = self.i16()? as u16 as usize;
+        self.charge(len)?;
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    fn payload(&mut self, id: u8, depth: usize) -> Result<Tag, FormatError> {
+        if depth > MAX_DEPTH {
+            return Err(FormatError::Corrupt("nesting too deep".to_owned()));
+        }
+
+        Ok(match id {
+            1 => Tag::Byte(self.u8()? as i8),
+            2 => Tag::Short(self.i16()?),
+            3 => Tag::Int(self.i32()?),
+            4 => Tag::Long(self.i64()?),
+            5 => {
+                self.take(4)?;
+                Tag::Other
+            }
+            6 => {
+                self.take(8)?;
+                Tag::Other
+            }
+            7 => {
+                let len = self.len(1)?;
+                self.charge(len)?;
+                Tag::ByteArray(self.take(len)?.to_vec())
+            }
+            8 => Tag::String(self.string()?),
+            9 => {
+                let element = self.u8()?;
+                let len = match min_size(element) {
+                    Some(size) => self.len(size)?,
+                    // Empty lists may be typed TAG_End, but nothing can be read from one.
+                    None => match self.len(0)? {
+                        0 => 0,
+                        _ => return Err(FormatError::Corrupt(format!("list of tag type {}", element))),
+                    },
+                };
+                self.charge(len.saturating_mul(mem::size_of::<Tag>()))?;
+                let mut list = Vec::with_capacity(len.min(self.data.len() - self.pos));
+                for _ in 0..len {
+                    list.push(self.payload(element, depth + 1)?);
+                }
+                Tag::List(list)
+            }
+            10 => {
+                let mut map = HashMap::new();
+                loop {
+                    let child = self.u8()?;
+                    if child == 0 {
+                        break;
+                    }
+                    let name = self.string()?;
+                    self.charge(mem::size_of::<(String, Tag)>())?;
+                    map.insert(name, self.payload(child, depth + 1)?);
+                }
+                Tag::Compound(map)
+            }
+            11 => {
+                let len = self.len(4)?;
+                self.take(len * 4)?;
+                Tag::Other
+            }
+            12 => {
+                let len = self.len(8)?;
+                self.take(len * 8)?;
+                Tag::Other
+            }
+            _ => return Err(FormatError::Corrupt(format!("unknown tag type {}", id))),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An uncompressed document whose root compound holds a single list named `l`.
+    fn document(element: u8, len: i32, payload: &[u8]) -> Vec<u8> {
+        let mut data = vec![10, 0, 0, 9, 0, 1, b'l', element];
+        data.extend_from_slice(&len.to_be_bytes());
+        data.extend_from_slice(payload);
+        data.push(0);
+        data
+    }
+
+    fn is_corrupt(data: &[u8]) -> bool {
+        matches!(read(data), Err(FormatError::Corrupt(_)))
+    }
+
+    #[test]
+    fn reads_lists() {
+        let tag = read(&document(3, 2, &[0, 0, 0, 1, 0, 0, 0, 2])).unwrap();
+        let list = tag.get("l").and_then(Tag::as_list).unwrap();
+        assert_eq!(list.iter().map(|tag| tag.as_i64().unwrap()).collect::<Vec<_>>(), [1, 2]);
+
+        let tag = read(&document(0, 0, &[])).unwrap();
+        assert!(tag.get("l").and_then(Tag::as_list).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_non_empty_end_lists() {
+        assert!(is_corrupt(&document(0, 1, &[])));
+        assert!(is_corrupt(&document(0, i32::MAX, &[])));
+    }
+
+    #[test]
+    fn rejects_unknown_element_types() {
+        assert!(is_corrupt(&document(13, 1, &[0; 16])));
+    }
+
+    #[test]
+    fn rejects_negative_lengths() {
+        assert!(is_corrupt(&document(1, -1, &[])));
+    }
+
+    #[test]
+    fn rejects_lengths_past_the_data() {
+        assert!(is_corrupt(&document(1, i32::MAX, &[])));
+        // Each long takes 8 bytes, so 3 can't fit in 16.
+        assert!(is_corrupt(&document(4, 3, &[0; 16])));
+        // Nested lists take at least 5 bytes each.
+        assert!(is_corrupt(&document(9, 4, &[0; 16])));
+    }
+
+    #[test]
+    fn charges_decoded_size_against_the_budget() {
+        let len = (MAX_DECOMPRESSED_SIZE as usize / mem::size_of::<Tag>() + 1) as i32;
+        let data = document(1, len, &vec![0; len as usize]);
+        assert!(is_corrupt(&data));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::formats::nbt::{self, Tag};
+use crate::formats::{is_air, FileMetadata, FormatError, Parser};
+
+/// Sponge schematics, versions 1 to 3, as written by WorldEdit.
+pub struct SpongeParser;
+
+impl Parser for SpongeParser {
+    fn format(&self) -> &'static str {
+        "sponge"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["schem"]
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        nbt::is_nbt(data)
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<FileMetadata, FormatError> {
+        let root = nbt::read(data)?;
+        // Version 3 nests everything under a `Schematic` compound.
+        let schematic = root.get("Schematic").unwrap_or(&root);
+
+        let version = schematic.get("Version").and_then(Tag::as_i64).ok_or(FormatError::Mismatch)?;
+        let dimension = |key: &str| {
+            schematic.get(key)
+                .and_then(Tag::as_i64)
+                .map(|v| v as u16 as i64)
+                .ok_or(FormatError::Mismatch)
+        };
+        let (width, height, length) = (dimension("Width")?, dimension("Height")?, dimension("Length")?);
+
+        let (palette, data, entities) = if version >= 3 {
+            let blocks = schematic.get("Blocks");
+            (
+                blocks.and_then(|b| b.get("Palette")),
+                blocks.and_then(|b| b.get("Data")),
+                schematic.get("Entities"),
+            )
+        } else {
+            (schematic.get("Palette"), schematic.get("BlockData"), schematic.get("Entities"))
+        };
+
+        let palette = palette.and_then(Tag::as_compound).ok_or(FormatError::Mismatch)?;
+        let data = data.and_then(Tag::as_bytes).ok_or(FormatError::Mismatch)?;
+
+        let air: Vec<i64> = palette
+            .iter()
+            .filter(|(name, _)| is_air(name.split('[').next().unwrap_or_default()))
+            .filter_map(|(_, index)| index.as_i64())
+            .collect();
+
+        // Block data is a run of varint palette indices, one per block.
+        let mut count = 0;
+        let mut total = 0;
+        let mut value: i64 = 0;
+        let mut shift = 0;
+        for byte in data {
+            value |= ((byte & 0x7f) as i64) << shift;
+            if byte & 0x80 == 0 {
+                if !air.contains(&value) {
+                    count += 1;
+                }
+                total += 1;
+                value = 0;
+                shift = 0;
+            } else {
+                shift += 7;
+                if shift > 35 {
+                    return Err(FormatError::Corrupt("varint too long".to_owned()));
+                }
+            }
+        }
+
+        if total != width * height * length {
+            return Err(FormatError::Corrupt("block data doesn't match dimensions".to_owned()));
+        }
+
+        Ok(FileMetadata {
+            version: Some(version),
+            data_version: schematic.get("DataVersion").and_then(Tag::as_i64),
+            width,
+            height,
+            length,
+            blocks: count,
+            entities: entities.and_then(Tag::as_list).map_or(0, |e| e.len() as i64),
+        })
+    }
+}
@@ -0,0 +1,64 @@
+use crate::formats::nbt::{self, Tag};
+use crate::formats::{is_air, FileMetadata, FormatError, Parser};
+
+/// Vanilla structure block files.
+pub struct StructureParser;
+
+impl Parser for StructureParser {
+    fn format(&self) -> &'static str {
+        "structure"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["nbt"]
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        nbt::is_nbt(data)
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<FileMetadata, FormatError> {
+        let root = nbt::read(data)?;
+
+        let size: Vec<i64> = root.get("size")
+            .and_then(Tag::as_list)
+            .map(|s| s.iter().filter_map(Tag::as_i64).collect())
+            .filter(|s: &Vec<i64>| s.len() == 3)
+            .ok_or(FormatError::Mismatch)?;
+
+        let blocks = root.get("blocks").and_then(Tag::as_list).ok_or(FormatError::Mismatch)?;
+
+        // Structures saved with random variants carry several palettes, the first one is the default.
+        let palette = root.get("palette")
+            .or_else(|| root.get("palettes").and_then(Tag::as_list).and_then(|p| p.first()))
+            .and_then(Tag::as_list)
+            .ok_or(FormatError::Mismatch)?;
+
+        let air: Vec<bool> = palette
+            .iter()
+            .map(|state| state.get("Name").and_then(Tag::as_str).is_some_and(is_air))
+            .collect();
+
+        let mut count = 0;
+        for block in blocks {
+            let state = block.get("state")
+                .and_then(Tag::as_i64)
+                .ok_or_else(|| FormatError::Corrupt("block without state".to_owned()))?;
+            match air.get(state as usize) {
+                Some(false) => count += 1,
+                Some(true) => {}
+                None => return Err(FormatError::Corrupt("block state out of palette".to_owned())),
+            }
+        }
+
+        Ok(FileMetadata {
+            version: None,
+            data_version: root.get("DataVersion").and_then(Tag::as_i64),
+            width: size[0],
+            height: size[1],
+            length: size[2],
+            blocks: count,
+            entities: root.get("entities").and_then(Tag::as_list).map_or(0, |e| e.len() as i64),
+        })
+    }
+}
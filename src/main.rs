@@ -1,9 +1,15 @@
 mod routes;
 mod database;
 mod models;
+mod tasks;
+mod storage;
+mod formats;
+mod archives;
+mod markdown;
+mod notifications;
 
 use actix_cors::Cors;
-use actix_web::web::Data;
+use actix_web::web::{Data, PayloadConfig};
 use actix_web::{App, HttpServer};
 use env_logger::Env;
 
@@ -17,6 +23,27 @@ async fn main() -> std::io::Result<()> {
         .await
         .expect("Failed to connect to database");
 
+    // Any arguments select a one-off archive command instead of serving.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if !archives::cli::run(&args, &postgre).await {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    tasks::publish::spawn(postgre.clone());
+    tasks::downloads::spawn(postgre.clone());
+    tasks::views::spawn(postgre.clone());
+    tasks::images::spawn(postgre.clone());
+    tasks::blobs::spawn(postgre.clone());
+    tasks::markdown::spawn(postgre.clone());
+    tasks::trending::spawn(postgre.clone());
+    tasks::badges::spawn(postgre.clone());
+
+    let hub = Data::new(notifications::live::Hub::default());
+    notifications::live::spawn(postgre.clone(), hub.clone());
+
     HttpServer::new(move || {
         let cors = {
             #[cfg(debug_assertions)]
@@ -44,6 +71,8 @@ async fn main() -> std::io::Result<()> {
             .wrap(cors)
             .configure(routes::config)
             .app_data(Data::new(postgre.clone()))
+            .app_data(hub.clone())
+            .app_data(PayloadConfig::new(64 * 1024 * 1024))
     })
         .bind(dotenvy::var("SERVER_ADDR").expect("`SERVER_ADDR` not in .env"))?
         .run()
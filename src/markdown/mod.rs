@@ -0,0 +1,36 @@
+use std::collections::HashSet;
+use ammonia::Builder;
+use lazy_static::lazy_static;
+use pulldown_cmark::{html, Options, Parser};
+
+lazy_static! {
+    static ref SANITIZER: Builder<'static> = {
+        let mut builder = Builder::empty();
+        builder
+            .add_tags([
+                "p", "br", "hr", "h1", "h2", "h3", "h4", "h5", "h6",
+                "strong", "em", "del", "code", "pre", "blockquote",
+                "ul", "ol", "li", "a", "img",
+                "table", "thead", "tbody", "tr", "th", "td",
+            ])
+            .add_tag_attributes("a", ["href", "title"])
+            .add_tag_attributes("img", ["src", "alt", "title"])
+            .add_tag_attributes("ol", ["start"])
+            .add_tag_attributes("th", ["align"])
+            .add_tag_attributes("td", ["align"])
+            .url_schemes(HashSet::from(["http", "https", "mailto"]))
+            // User content shouldn't pass on ranking or be trusted by crawlers.
+            .link_rel(Some("nofollow ugc"));
+        builder
+    };
+}
+
+/// Renders CommonMark to HTML, keeping only an allowlist of tags and attributes.
+pub fn render(source: &str) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
+
+    let mut unsafe_html = String::with_capacity(source.len() * 3 / 2);
+    html::push_html(&mut unsafe_html, Parser::new_ext(source, options));
+
+    SANITIZER.clean(&unsafe_html).to_string()
+}
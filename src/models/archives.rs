@@ -0,0 +1,64 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::models::creators::CreatorRole;
+use crate::models::ids::StructureID;
+use crate::models::structures::{License, Visibility};
+
+/// Bumped whenever the manifest layout changes incompatibly.
+pub const MANIFEST_VERSION: i32 = 1;
+
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: i32,
+    pub structure: ManifestStructure,
+    pub creators: Vec<ManifestCreator>,
+    pub files: Vec<ManifestFile>,
+    pub images: Vec<ManifestImage>,
+    /// URLs of files and images hosted outside this instance's storage, which were left out.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ManifestStructure {
+    pub name: String,
+    pub summary: String,
+    pub description: String,
+    pub version: String,
+    pub changelog: String,
+    pub visibility: Visibility,
+    pub published: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub license: Option<License>,
+    pub created: NaiveDateTime,
+}
+
+/// Creators are matched by user name on import, as ids differ between instances.
+#[derive(Serialize, Deserialize)]
+pub struct ManifestCreator {
+    pub name: String,
+    pub role: CreatorRole,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub name: String,
+    /// Location of the content inside the archive.
+    pub path: String,
+    pub checksum: String,
+    pub format: Option<String>,
+    pub metadata: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ManifestImage {
+    pub path: String,
+    pub checksum: String,
+}
+
+pub struct Imported {
+    pub structure: StructureID,
+    /// Creators in the manifest with no user of the same name on this instance.
+    pub unmatched: Vec<String>,
+}
@@ -0,0 +1,52 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// An achievement shown on profiles, awarded by `tasks::badges`.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Badge {
+    /// Co-created a public structure.
+    FirstUpload,
+    /// Structures the user co-created were downloaded 1,000 times.
+    ThousandDownloads,
+    TenThousandDownloads,
+    HundredFollowers,
+    /// Held for as long as the user is staff.
+    Staff,
+}
+
+impl Badge {
+    pub const ALL: [Badge; 5] = [
+        Badge::FirstUpload,
+        Badge::ThousandDownloads,
+        Badge::TenThousandDownloads,
+        Badge::HundredFollowers,
+        Badge::Staff,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Badge::FirstUpload => "first_upload",
+            Badge::ThousandDownloads => "thousand_downloads",
+            Badge::TenThousandDownloads => "ten_thousand_downloads",
+            Badge::HundredFollowers => "hundred_followers",
+            Badge::Staff => "staff",
+        }
+    }
+
+    pub fn parse(s: &str) -> Badge {
+        match s {
+            "first_upload" => Badge::FirstUpload,
+            "thousand_downloads" => Badge::ThousandDownloads,
+            "ten_thousand_downloads" => Badge::TenThousandDownloads,
+            "hundred_followers" => Badge::HundredFollowers,
+            _ => Badge::Staff,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UserBadge {
+    pub badge: Badge,
+    pub awarded: NaiveDateTime,
+}
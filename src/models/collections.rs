@@ -0,0 +1,85 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use crate::models::ids::{CollectionID, StructureID};
+use crate::models::structures::StructurePreview;
+use crate::models::users::UserPreview;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionVisibility {
+    Private,
+    Unlisted,
+    Public,
+}
+
+impl CollectionVisibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CollectionVisibility::Private => "private",
+            CollectionVisibility::Unlisted => "unlisted",
+            CollectionVisibility::Public => "public",
+        }
+    }
+
+    pub fn parse(s: &str) -> CollectionVisibility {
+        match s {
+            "unlisted" => CollectionVisibility::Unlisted,
+            "public" => CollectionVisibility::Public,
+            _ => CollectionVisibility::Private,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Collection {
+    pub id: CollectionID,
+    pub owner: UserPreview,
+    pub name: String,
+    pub description: String,
+    pub visibility: CollectionVisibility,
+    pub items: Vec<CollectionItem>,
+    pub created: NaiveDateTime,
+    pub updated: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct CollectionItem {
+    pub structure: StructurePreview,
+    pub note: Option<String>,
+    pub added: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct CollectionPreview {
+    pub id: CollectionID,
+    pub name: String,
+    pub description: String,
+    pub visibility: CollectionVisibility,
+    pub items: i64,
+    pub updated: NaiveDateTime,
+}
+
+#[derive(Deserialize)]
+pub struct CollectionCreate {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub visibility: Option<CollectionVisibility>,
+}
+
+#[derive(Deserialize)]
+pub struct CollectionUpdate {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub visibility: Option<CollectionVisibility>,
+}
+
+#[derive(Deserialize)]
+pub struct CollectionItemCreate {
+    pub structure: Option<StructureID>,
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CollectionItemsOrder {
+    pub structures: Vec<StructureID>,
+}
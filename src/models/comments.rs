@@ -0,0 +1,71 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use crate::models::ids::CommentID;
+use crate::models::users::UserPreview;
+
+#[derive(Serialize, Clone, Copy)]
+pub enum CommentRemoval {
+    Author,
+    Moderator,
+}
+
+#[derive(Serialize)]
+pub struct Comment {
+    pub id: CommentID,
+    pub author: UserPreview,
+    pub body: Option<String>,
+    /// `body` rendered from markdown and sanitized.
+    pub body_html: Option<String>,
+    pub removed: Option<CommentRemoval>,
+    pub replies: Vec<Comment>,
+    pub created: NaiveDateTime,
+    pub edited: Option<NaiveDateTime>,
+}
+
+pub struct DbComment {
+    pub id: i64,
+    pub parent: Option<i64>,
+    pub author_name: String,
+    pub author_avatar: Option<String>,
+    pub author_slug: Option<String>,
+    pub body: String,
+    pub body_html: Option<String>,
+    pub removed: Option<String>,
+    pub created: NaiveDateTime,
+    pub edited: Option<NaiveDateTime>,
+}
+
+impl From<DbComment> for Comment {
+    fn from(comment: DbComment) -> Self {
+        let removed = comment.removed.map(|removed| match removed.as_str() {
+            "author" => CommentRemoval::Author,
+            _ => CommentRemoval::Moderator,
+        });
+
+        Comment {
+            id: CommentID::from(comment.id),
+            author: UserPreview {
+                name: comment.author_name,
+                avatar: comment.author_avatar,
+                slug: comment.author_slug,
+            },
+            body: if removed.is_some() { None } else { Some(comment.body) },
+            body_html: if removed.is_some() { None } else { comment.body_html },
+            removed,
+            replies: Vec::new(),
+            created: comment.created,
+            edited: comment.edited,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CommentCreate {
+    pub body: Option<String>,
+    pub parent: Option<CommentID>,
+}
+
+#[derive(Deserialize)]
+pub struct CommentEdit {
+    pub body: Option<String>,
+}
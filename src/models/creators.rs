@@ -0,0 +1,78 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use crate::models::ids::{StructureID, UserID};
+use crate::models::users::UserPreview;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CreatorRole {
+    Owner,
+    Maintainer,
+    Contributor,
+}
+
+impl CreatorRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CreatorRole::Owner => "owner",
+            CreatorRole::Maintainer => "maintainer",
+            CreatorRole::Contributor => "contributor",
+        }
+    }
+
+    pub fn parse(s: &str) -> CreatorRole {
+        match s {
+            "owner" => CreatorRole::Owner,
+            "maintainer" => CreatorRole::Maintainer,
+            _ => CreatorRole::Contributor,
+        }
+    }
+
+    /// Lower is more privileged.
+    pub fn rank(&self) -> u8 {
+        match self {
+            CreatorRole::Owner => 0,
+            CreatorRole::Maintainer => 1,
+            CreatorRole::Contributor => 2,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Creator {
+    pub id: UserID,
+    pub name: String,
+    pub avatar: Option<String>,
+    pub slug: Option<String>,
+    pub role: CreatorRole,
+}
+
+#[derive(Serialize)]
+pub struct Invitation {
+    pub structure: StructureID,
+    pub name: String,
+    pub inviter: UserPreview,
+    pub role: CreatorRole,
+    pub created: NaiveDateTime,
+}
+
+#[derive(Deserialize)]
+pub struct InvitationCreate {
+    pub user: Option<UserID>,
+    pub role: Option<CreatorRole>,
+}
+
+#[derive(Deserialize)]
+pub struct CreatorUpdate {
+    pub role: Option<CreatorRole>,
+}
+
+#[derive(Deserialize)]
+pub struct CreatorsOrder {
+    pub creators: Vec<UserID>,
+}
+
+#[derive(Deserialize)]
+pub struct OwnershipTransfer {
+    pub user: Option<UserID>,
+}
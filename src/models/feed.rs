@@ -0,0 +1,77 @@
+use chrono::{DateTime, NaiveDateTime};
+use serde::de::Visitor;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::Formatter;
+use destru::{decode_sqids, encode_sqids};
+use crate::models::ids::{StructureID, STRUCTURE_FLAG};
+use crate::models::structures::StructurePreview;
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedActivity {
+    Published,
+    Updated,
+}
+
+#[derive(Serialize)]
+pub struct FeedItem {
+    pub structure: StructurePreview,
+    pub activity: FeedActivity,
+    pub version: Option<String>,
+    pub time: NaiveDateTime,
+}
+
+/// Position in the feed, as the time and structure of the last item seen.
+///
+/// Publish times are often identical for scheduled structures, so the time alone can't page.
+#[derive(Clone, Copy)]
+pub struct FeedCursor {
+    pub time: NaiveDateTime,
+    pub structure: StructureID,
+}
+
+impl Serialize for FeedCursor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let structure = encode_sqids(STRUCTURE_FLAG, self.structure.0).map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&format!("{}.{}", self.time.and_utc().timestamp_micros(), structure))
+    }
+}
+
+impl<'de> Deserialize<'de> for FeedCursor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct CursorVisitor;
+
+        impl Visitor<'_> for CursorVisitor {
+            type Value = FeedCursor;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("a valid feed cursor string")
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                let (time, structure) = s.split_once('.').ok_or_else(|| E::custom("missing separator"))?;
+
+                let time = time.parse().ok()
+                    .and_then(DateTime::from_timestamp_micros)
+                    .ok_or_else(|| E::custom("invalid time"))?;
+                let structure = decode_sqids(STRUCTURE_FLAG, structure).map_err(E::custom)?;
+
+                Ok(FeedCursor {
+                    time: time.naive_utc(),
+                    structure: StructureID(structure),
+                })
+            }
+        }
+
+        deserializer.deserialize_str(CursorVisitor)
+    }
+}
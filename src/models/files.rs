@@ -1,8 +1,96 @@
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::models::ids::FileID;
 
 #[derive(Serialize, Deserialize)]
 pub struct File {
+    pub id: FileID,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
     pub url: String,
+    /// Hex-encoded SHA-256 of the content, absent for files stored before content addressing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blurhash: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<FileVariant>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
     pub created: NaiveDateTime,
-}
\ No newline at end of file
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FileVariant {
+    pub size: String,
+    pub format: String,
+    pub url: String,
+    pub width: i32,
+    pub height: i32,
+}
+
+pub struct DbFile {
+    pub id: FileID,
+    pub name: Option<String>,
+    pub url: String,
+    pub checksum: Option<String>,
+    pub size: Option<i64>,
+    pub format: Option<String>,
+    pub metadata: Option<Value>,
+    pub created: NaiveDateTime,
+}
+
+impl From<DbFile> for File {
+    fn from(file: DbFile) -> Self {
+        File {
+            id: file.id,
+            name: file.name,
+            url: file.url,
+            checksum: file.checksum,
+            size: file.size,
+            width: None,
+            height: None,
+            blurhash: None,
+            variants: Vec::new(),
+            format: file.format,
+            metadata: file.metadata,
+            created: file.created,
+        }
+    }
+}
+
+pub struct DbImage {
+    pub id: i64,
+    pub url: String,
+    pub checksum: Option<String>,
+    pub size: Option<i64>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub blurhash: Option<String>,
+    pub created: NaiveDateTime,
+}
+
+pub struct DbImageVariant {
+    pub image: i64,
+    pub size: String,
+    pub format: String,
+    pub url: String,
+    pub width: i32,
+    pub height: i32,
+}
+
+#[derive(Deserialize)]
+pub struct FileUpload {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub changelog: Option<String>,
+}
@@ -5,6 +5,10 @@ use destru::{decode_sqids, encode_sqids};
 
 pub const USER_FLAG: u8 = 0;
 pub const STRUCTURE_FLAG: u8 = 1;
+pub const FILE_FLAG: u8 = 2;
+pub const COMMENT_FLAG: u8 = 3;
+pub const COLLECTION_FLAG: u8 = 4;
+pub const NOTIFICATION_FLAG: u8 = 5;
 
 macro_rules! define_id {
     ($name:ident, $flag:expr, $expecting:literal) => {
@@ -56,4 +60,8 @@ macro_rules! define_id {
 }
 
 define_id!(UserID, USER_FLAG, "UserID");
-define_id!(StructureID, STRUCTURE_FLAG, "StructureID");
\ No newline at end of file
+define_id!(StructureID, STRUCTURE_FLAG, "StructureID");
+define_id!(FileID, FILE_FLAG, "FileID");
+define_id!(CommentID, COMMENT_FLAG, "CommentID");
+define_id!(CollectionID, COLLECTION_FLAG, "CollectionID");
+define_id!(NotificationID, NOTIFICATION_FLAG, "NotificationID");
@@ -4,3 +4,11 @@ pub mod files;
 pub mod users;
 pub mod ids;
 pub mod responses;
+pub mod comments;
+pub mod versions;
+pub mod creators;
+pub mod archives;
+pub mod collections;
+pub mod feed;
+pub mod notifications;
+pub mod badges;
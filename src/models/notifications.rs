@@ -0,0 +1,195 @@
+use chrono::NaiveDateTime;
+use serde::de::Visitor;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::Formatter;
+use destru::{decode_sqids, encode_sqids};
+use crate::models::ids::{CommentID, NotificationID, StructureID, NOTIFICATION_FLAG};
+use crate::models::users::UserPreview;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// A comment on a structure the user created.
+    Comment,
+    /// A reply to one of the user's comments.
+    Reply,
+    /// A new rating on a structure the user created.
+    Rating,
+    Invitation,
+    Follow,
+    /// Staff removed the user's content.
+    Moderation,
+}
+
+impl NotificationKind {
+    pub const ALL: [NotificationKind; 6] = [
+        NotificationKind::Comment,
+        NotificationKind::Reply,
+        NotificationKind::Rating,
+        NotificationKind::Invitation,
+        NotificationKind::Follow,
+        NotificationKind::Moderation,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::Comment => "comment",
+            NotificationKind::Reply => "reply",
+            NotificationKind::Rating => "rating",
+            NotificationKind::Invitation => "invitation",
+            NotificationKind::Follow => "follow",
+            NotificationKind::Moderation => "moderation",
+        }
+    }
+
+    pub fn parse(s: &str) -> NotificationKind {
+        match s {
+            "comment" => NotificationKind::Comment,
+            "reply" => NotificationKind::Reply,
+            "rating" => NotificationKind::Rating,
+            "invitation" => NotificationKind::Invitation,
+            "follow" => NotificationKind::Follow,
+            _ => NotificationKind::Moderation,
+        }
+    }
+}
+
+/// Where notifications are delivered.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationChannel {
+    InApp,
+}
+
+impl NotificationChannel {
+    pub const ALL: [NotificationChannel; 1] = [NotificationChannel::InApp];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationChannel::InApp => "in_app",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Notification {
+    pub id: NotificationID,
+    pub kind: NotificationKind,
+    pub actor: Option<UserPreview>,
+    pub structure: Option<NotificationStructure>,
+    pub comment: Option<CommentID>,
+    pub read: bool,
+    pub created: NaiveDateTime,
+}
+
+pub struct DbNotification {
+    pub id: NotificationID,
+    pub kind: String,
+    pub actor_name: Option<String>,
+    pub actor_avatar: Option<String>,
+    pub actor_slug: Option<String>,
+    pub structure: Option<i64>,
+    pub structure_name: Option<String>,
+    pub comment: Option<i64>,
+    pub read: bool,
+    pub created: NaiveDateTime,
+}
+
+impl From<DbNotification> for Notification {
+    fn from(db: DbNotification) -> Self {
+        Notification {
+            id: db.id,
+            kind: NotificationKind::parse(&db.kind),
+            actor: db.actor_name.map(|name| UserPreview {
+                name,
+                avatar: db.actor_avatar,
+                slug: db.actor_slug,
+            }),
+            structure: db.structure.zip(db.structure_name).map(|(id, name)| NotificationStructure {
+                id: StructureID::from(id),
+                name,
+            }),
+            comment: db.comment.map(CommentID::from),
+            read: db.read,
+            created: db.created,
+        }
+    }
+}
+
+/// Position in the notification list, as the read state and id of the last item seen.
+///
+/// The read state is carried along rather than looked up, so marking the item read or deleting it
+/// doesn't move the position.
+#[derive(Clone, Copy)]
+pub struct NotificationCursor {
+    pub read: bool,
+    pub notification: NotificationID,
+}
+
+impl Serialize for NotificationCursor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let notification = encode_sqids(NOTIFICATION_FLAG, self.notification.0).map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&format!("{}.{}", self.read as u8, notification))
+    }
+}
+
+impl<'de> Deserialize<'de> for NotificationCursor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct CursorVisitor;
+
+        impl Visitor<'_> for CursorVisitor {
+            type Value = NotificationCursor;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("a valid notification cursor string")
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                let (read, notification) = s.split_once('.').ok_or_else(|| E::custom("missing separator"))?;
+
+                let read = match read {
+                    "0" => false,
+                    "1" => true,
+                    _ => return Err(E::custom("invalid read state")),
+                };
+                let notification = decode_sqids(NOTIFICATION_FLAG, notification).map_err(E::custom)?;
+
+                Ok(NotificationCursor {
+                    read,
+                    notification: NotificationID(notification),
+                })
+            }
+        }
+
+        deserializer.deserialize_str(CursorVisitor)
+    }
+}
+
+#[derive(Serialize)]
+pub struct NotificationStructure {
+    pub id: StructureID,
+    pub name: String,
+}
+
+#[derive(Serialize)]
+pub struct NotificationPreference {
+    pub kind: NotificationKind,
+    pub channel: NotificationChannel,
+    pub enabled: bool,
+}
+
+#[derive(Deserialize)]
+pub struct NotificationPreferenceUpdate {
+    pub kind: Option<NotificationKind>,
+    pub channel: Option<NotificationChannel>,
+    pub enabled: Option<bool>,
+}
@@ -11,4 +11,15 @@ pub struct Pagination {
     pub page: i64,
     pub size: i64,
     pub total: i64,
-}
\ No newline at end of file
+}
+#[derive(Serialize, Deserialize)]
+pub struct CursorParams<T> {
+    pub cursor: Option<T>,
+    pub size: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct CursorPagination<T> {
+    pub next: Option<T>,
+    pub size: i64,
+}
@@ -1,8 +1,15 @@
 use serde::Serialize;
-use crate::models::ids::UserID;
-use crate::models::pagination::Pagination;
+use crate::models::comments::Comment;
+use crate::models::feed::{FeedCursor, FeedItem};
+use crate::models::creators::Invitation;
+use crate::models::files::File;
+use crate::models::collections::{Collection, CollectionPreview};
+use crate::models::ids::{CollectionID, CommentID, StructureID, UserID};
+use crate::models::notifications::{Notification, NotificationCursor, NotificationPreference};
+use crate::models::pagination::{CursorPagination, Pagination};
 use crate::models::structures::{Structure, StructurePreview};
-use crate::models::users::User;
+use crate::models::users::{PrivacySettings, User, UserSummary};
+use crate::models::versions::Version;
 
 #[derive(Serialize)]
 pub struct StructureResponse {
@@ -20,6 +27,11 @@ pub enum UserError {
     InvalidName,
     InvalidPassword,
     NameExists,
+    ReservedName,
+    InvalidSlug,
+    SlugExists,
+    BioTooLong,
+    RenameTooSoon,
 }
 
 #[derive(Serialize)]
@@ -27,6 +39,11 @@ pub struct RegisterErrorResponse {
     pub reason: UserError,
 }
 
+#[derive(Serialize)]
+pub struct UserErrorResponse {
+    pub reason: UserError,
+}
+
 #[derive(Serialize)]
 pub struct LoginResponse {
     pub id: UserID,
@@ -38,3 +55,258 @@ pub struct LoginResponse {
 pub struct UserResponse {
     pub user: User,
 }
+
+#[derive(Serialize)]
+pub struct PrivacyResponse {
+    pub privacy: PrivacySettings,
+}
+
+#[derive(Serialize)]
+pub struct UsersResponse {
+    pub users: Vec<UserSummary>,
+    pub pagination: Pagination,
+}
+
+#[derive(Serialize)]
+pub struct UserHandleResponse {
+    pub user: User,
+    /// The handle the user is canonically reached under.
+    pub handle: String,
+    /// Whether the requested handle differs from the canonical one.
+    pub redirect: bool,
+}
+
+#[derive(Serialize)]
+pub struct CommentResponse {
+    pub comment: Comment,
+}
+
+#[derive(Serialize)]
+pub struct CommentsResponse {
+    pub comments: Vec<Comment>,
+    pub pagination: CursorPagination<CommentID>,
+}
+
+#[derive(Serialize)]
+pub enum CommentError {
+    InvalidBody,
+    InvalidParent,
+}
+
+#[derive(Serialize)]
+pub struct CommentErrorResponse {
+    pub reason: CommentError,
+}
+
+#[derive(Serialize)]
+pub struct VersionsResponse {
+    pub versions: Vec<Version>,
+}
+
+#[derive(Serialize)]
+pub struct VersionResponse {
+    pub version: Version,
+    pub description: String,
+    pub description_html: String,
+    pub files: Vec<File>,
+}
+
+#[derive(Serialize)]
+pub enum VersionError {
+    InvalidLabel,
+    InvalidChangelog,
+    InvalidDescription,
+    LabelExists,
+}
+
+#[derive(Serialize)]
+pub struct VersionErrorResponse {
+    pub reason: VersionError,
+}
+
+#[derive(Serialize)]
+pub enum StructureError {
+    InvalidVisibility,
+    InvalidSchedule,
+    NoteTooLong,
+    InvalidLicense,
+}
+
+#[derive(Serialize)]
+pub struct ForkResponse {
+    pub structure: StructureID,
+}
+
+#[derive(Serialize)]
+pub struct StructureErrorResponse {
+    pub reason: StructureError,
+}
+
+#[derive(Serialize)]
+pub struct InvitationsResponse {
+    pub invitations: Vec<Invitation>,
+}
+
+#[derive(Serialize)]
+pub enum CreatorError {
+    InvalidRole,
+    InvalidUser,
+    AlreadyCreator,
+    NotCreator,
+    InvalidOrder,
+    OwnerCannotLeave,
+}
+
+#[derive(Serialize)]
+pub struct CreatorErrorResponse {
+    pub reason: CreatorError,
+}
+
+#[derive(Serialize)]
+pub enum ImageError {
+    TooLarge,
+    UnsupportedFormat,
+}
+
+#[derive(Serialize)]
+pub struct ImageErrorResponse {
+    pub reason: ImageError,
+}
+
+#[derive(Serialize)]
+pub enum FileError {
+    TooLarge,
+    InvalidName,
+    InvalidVersion,
+    VersionExists,
+    UnsupportedFormat,
+    FormatMismatch,
+    CorruptFile,
+}
+
+#[derive(Serialize)]
+pub struct FileErrorResponse {
+    pub reason: FileError,
+}
+
+#[derive(Serialize)]
+pub struct ImportResponse {
+    pub structure: StructureID,
+    pub unmatched: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub enum ImportError {
+    InvalidArchive,
+    InvalidManifest,
+    UnsupportedVersion,
+    MissingEntry,
+    ChecksumMismatch,
+    InvalidFile,
+    NoCreators,
+}
+
+#[derive(Serialize)]
+pub struct ImportErrorResponse {
+    pub reason: ImportError,
+}
+
+#[derive(Serialize)]
+pub struct CollectionResponse {
+    pub collection: Collection,
+}
+
+#[derive(Serialize)]
+pub struct CollectionsResponse {
+    pub collections: Vec<CollectionPreview>,
+    pub pagination: Pagination,
+}
+
+#[derive(Serialize)]
+pub struct CollectionCreateResponse {
+    pub collection: CollectionID,
+}
+
+#[derive(Serialize)]
+pub enum CollectionError {
+    InvalidName,
+    InvalidDescription,
+    NoteTooLong,
+    TooManyItems,
+    InvalidItems,
+    StructureNotFound,
+}
+
+#[derive(Serialize)]
+pub struct CollectionErrorResponse {
+    pub reason: CollectionError,
+}
+
+#[derive(Serialize)]
+pub struct RatingResponse {
+    pub rating: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub enum RatingError {
+    InvalidRating,
+    OwnStructure,
+}
+
+#[derive(Serialize)]
+pub struct RatingErrorResponse {
+    pub reason: RatingError,
+}
+
+#[derive(Serialize)]
+pub enum FollowError {
+    OwnAccount,
+}
+
+#[derive(Serialize)]
+pub struct FollowErrorResponse {
+    pub reason: FollowError,
+}
+
+#[derive(Serialize)]
+pub enum BlockError {
+    OwnAccount,
+    Blocked,
+}
+
+#[derive(Serialize)]
+pub struct BlockErrorResponse {
+    pub reason: BlockError,
+}
+
+#[derive(Serialize)]
+pub struct FeedResponse {
+    pub items: Vec<FeedItem>,
+    pub pagination: CursorPagination<FeedCursor>,
+}
+
+#[derive(Serialize)]
+pub struct NotificationsResponse {
+    pub notifications: Vec<Notification>,
+    pub pagination: CursorPagination<NotificationCursor>,
+}
+
+#[derive(Serialize)]
+pub struct UnreadResponse {
+    pub unread: i64,
+}
+
+#[derive(Serialize)]
+pub struct NotificationPreferencesResponse {
+    pub preferences: Vec<NotificationPreference>,
+}
+
+#[derive(Serialize)]
+pub enum NotificationError {
+    InvalidPreference,
+}
+
+#[derive(Serialize)]
+pub struct NotificationErrorResponse {
+    pub reason: NotificationError,
+}
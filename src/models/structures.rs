@@ -2,7 +2,7 @@ use crate::models::files::File;
 use crate::models::ids::StructureID;
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use crate::models::users::UserPreview;
+use crate::models::creators::Creator;
 
 #[derive(Serialize)]
 pub struct Structure {
@@ -10,25 +10,214 @@ pub struct Structure {
     pub name: String,
     pub summary: String,
     pub description: String,
+    pub description_html: String,
+    pub version: Option<String>,
     pub files: Vec<File>,
     pub images: Vec<File>,
-    pub creators: Vec<UserPreview>,
+    pub creators: Vec<Creator>,
+    pub downloads: i64,
+    pub ratings: i64,
+    /// Mean of all ratings, absent until the first one.
+    pub rating: Option<f64>,
+    pub rating_score: f64,
+    pub visibility: Visibility,
+    pub published: Option<NaiveDateTime>,
+    pub parent: Option<StructureParent>,
+    pub forks: i64,
+    pub license: Option<License>,
     pub created: NaiveDateTime,
 }
 
+/// The structure a fork was derived from.
+#[derive(Serialize)]
+pub struct StructureParent {
+    pub id: StructureID,
+    pub name: String,
+    pub note: Option<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct DbStructure {
     pub id: StructureID,
     pub name: String,
     pub summary: String,
     pub description: String,
+    pub description_html: Option<String>,
+    pub visibility: String,
+    pub published: Option<NaiveDateTime>,
+    pub parent: Option<i64>,
+    pub parent_note: Option<String>,
+    pub license: Option<String>,
+    pub license_text: Option<String>,
+    pub requires_attribution: bool,
+    pub allows_commercial_use: bool,
     pub created: NaiveDateTime,
 }
 
+/// SPDX identifiers creators can pick from, besides `custom`.
+pub const LICENSES: [&str; 9] = [
+    "CC0-1.0",
+    "CC-BY-4.0",
+    "CC-BY-SA-4.0",
+    "CC-BY-NC-4.0",
+    "CC-BY-NC-SA-4.0",
+    "CC-BY-ND-4.0",
+    "CC-BY-NC-ND-4.0",
+    "MIT",
+    "LicenseRef-All-Rights-Reserved",
+];
+
+pub const CUSTOM_LICENSE: &str = "custom";
+
+const MAX_LICENSE_TEXT_LENGTH: usize = 20000;
+
+/// No license clears it; custom licenses carry their own text and picked ones must not.
+pub fn is_valid_license(license: Option<&str>, text: Option<&str>) -> bool {
+    match license {
+        None => text.is_none(),
+        Some(CUSTOM_LICENSE) => text.is_some_and(|t| !t.trim().is_empty() && t.chars().count() <= MAX_LICENSE_TEXT_LENGTH),
+        Some(license) => LICENSES.contains(&license) && text.is_none(),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct License {
+    pub id: String,
+    /// Only set for custom licenses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    pub requires_attribution: bool,
+    pub allows_commercial_use: bool,
+}
+
+#[derive(Deserialize)]
+pub struct LicenseUpdate {
+    pub license: Option<String>,
+    pub text: Option<String>,
+    pub requires_attribution: Option<bool>,
+    pub allows_commercial_use: Option<bool>,
+}
+
 #[derive(Serialize)]
 pub struct StructurePreview {
     pub id: StructureID,
     pub name: String,
     pub image: Option<String>,
     pub creator: Option<String>,
-}
\ No newline at end of file
+    pub ratings: i64,
+    pub rating: Option<f64>,
+    pub rating_score: f64,
+}
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum StructureSort {
+    #[default]
+    Default,
+    Downloads,
+    TopRated,
+}
+
+impl StructureSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StructureSort::Default => "default",
+            StructureSort::Downloads => "downloads",
+            StructureSort::TopRated => "top_rated",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StructuresParams {
+    pub sort: Option<StructureSort>,
+    pub license: Option<String>,
+}
+
+/// Rolling window the trending feed is computed over.
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum TrendingWindow {
+    Day,
+    #[default]
+    Week,
+    Month,
+}
+
+impl TrendingWindow {
+    pub const ALL: [TrendingWindow; 3] = [TrendingWindow::Day, TrendingWindow::Week, TrendingWindow::Month];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrendingWindow::Day => "day",
+            TrendingWindow::Week => "week",
+            TrendingWindow::Month => "month",
+        }
+    }
+
+    pub fn days(&self) -> i32 {
+        match self {
+            TrendingWindow::Day => 1,
+            TrendingWindow::Week => 7,
+            TrendingWindow::Month => 30,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TrendingParams {
+    pub window: Option<TrendingWindow>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    Draft,
+    Private,
+    Unlisted,
+    Public,
+}
+
+impl Visibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Visibility::Draft => "draft",
+            Visibility::Private => "private",
+            Visibility::Unlisted => "unlisted",
+            Visibility::Public => "public",
+        }
+    }
+
+    pub fn parse(s: &str) -> Visibility {
+        match s {
+            "private" => Visibility::Private,
+            "unlisted" => Visibility::Unlisted,
+            "public" => Visibility::Public,
+            _ => Visibility::Draft,
+        }
+    }
+
+    /// Whether only the creators of a structure may see it.
+    pub fn is_restricted(&self) -> bool {
+        matches!(self, Visibility::Draft | Visibility::Private)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct StructurePublish {
+    pub scheduled: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize)]
+pub struct StructureVisibility {
+    pub visibility: Option<Visibility>,
+}
+
+#[derive(Deserialize)]
+pub struct StructureFork {
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct StructureRating {
+    pub rating: Option<i32>,
+}
@@ -1,4 +1,7 @@
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use crate::models::badges::UserBadge;
+use crate::models::files::FileVariant;
 use crate::models::ids::UserID;
 
 #[derive(Serialize, Deserialize)]
@@ -6,8 +9,89 @@ pub struct User {
     pub id: UserID,
     pub name: String,
     pub avatar: Option<String>,
+    /// Square crops of the avatar at each size.
+    pub avatars: Vec<FileVariant>,
     pub slug: Option<String>,
     pub bio: Option<String>,
+    pub bio_html: Option<String>,
+    /// Whether the profile only shows the name to other users.
+    pub private: bool,
+    pub stats: Option<UserStats>,
+    pub badges: Vec<UserBadge>,
+}
+
+impl User {
+    /// What other users see of a private profile.
+    pub fn into_private(self) -> User {
+        User {
+            id: self.id,
+            name: self.name,
+            avatar: None,
+            avatars: Vec::new(),
+            slug: None,
+            bio: None,
+            bio_html: None,
+            private: true,
+            stats: None,
+            badges: Vec::new(),
+        }
+    }
+}
+
+/// Counts over the public, published structures the user co-created.
+#[derive(Serialize, Deserialize)]
+pub struct UserStats {
+    pub structures: i64,
+    pub downloads: i64,
+    /// Ratings others left on those structures.
+    pub ratings: i64,
+    /// Left out when the user hides their followers from the viewer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub followers: Option<i64>,
+    /// Left out when the user hides who they follow from the viewer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub following: Option<i64>,
+    pub joined: NaiveDateTime,
+}
+
+pub struct DbUser {
+    pub id: UserID,
+    pub name: String,
+    pub avatar: Option<String>,
+    pub slug: Option<String>,
+    pub bio: Option<String>,
+    pub bio_html: Option<String>,
+    pub private: bool,
+    pub created: NaiveDateTime,
+    pub structures: i64,
+    pub downloads: i64,
+    pub ratings: i64,
+    pub followers: i64,
+    pub following: i64,
+}
+
+impl DbUser {
+    pub fn into_user(self, avatars: Vec<FileVariant>, badges: Vec<UserBadge>) -> User {
+        User {
+            id: self.id,
+            name: self.name,
+            avatar: self.avatar,
+            avatars,
+            slug: self.slug,
+            bio: self.bio,
+            bio_html: self.bio_html,
+            private: self.private,
+            stats: Some(UserStats {
+                structures: self.structures,
+                downloads: self.downloads,
+                ratings: self.ratings,
+                followers: Some(self.followers),
+                following: Some(self.following),
+                joined: self.created,
+            }),
+            badges,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -17,6 +101,55 @@ pub struct UserPreview {
     pub slug: Option<String>,
 }
 
+/// A user as listed in the directory.
+#[derive(Serialize)]
+pub struct UserSummary {
+    pub id: UserID,
+    pub name: String,
+    pub avatar: Option<String>,
+    pub slug: Option<String>,
+    pub structures: i64,
+    /// When the user joined, hidden for private users.
+    pub created: Option<NaiveDateTime>,
+}
+
+impl UserSummary {
+    /// What other users see of a private user in listings.
+    pub fn into_private(self) -> UserSummary {
+        UserSummary {
+            avatar: None,
+            slug: None,
+            structures: 0,
+            created: None,
+            ..self
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum UserSort {
+    Relevance,
+    Joined,
+    Structures,
+}
+
+impl UserSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserSort::Relevance => "relevance",
+            UserSort::Joined => "joined",
+            UserSort::Structures => "structures",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UsersParams {
+    pub q: Option<String>,
+    pub sort: Option<UserSort>,
+}
+
 #[derive(Deserialize)]
 pub struct UserRegister {
     pub name: Option<String>,
@@ -28,4 +161,29 @@ pub struct UserLogin {
     pub name: Option<String>,
     pub password: Option<String>,
     pub remember: Option<bool>,
-}
\ No newline at end of file
+}
+#[derive(Serialize)]
+pub struct PrivacySettings {
+    /// Shows other users only the name, and hides the user from the directory.
+    pub private: bool,
+    /// Hides who the user follows.
+    pub hide_activity: bool,
+    pub hide_collections: bool,
+    pub hide_followers: bool,
+}
+
+#[derive(Deserialize)]
+pub struct PrivacyUpdate {
+    pub private: Option<bool>,
+    pub hide_activity: Option<bool>,
+    pub hide_collections: Option<bool>,
+    pub hide_followers: Option<bool>,
+}
+
+/// Empty strings clear the slug or bio.
+#[derive(Deserialize)]
+pub struct UserUpdate {
+    pub name: Option<String>,
+    pub slug: Option<String>,
+    pub bio: Option<String>,
+}
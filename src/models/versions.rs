@@ -0,0 +1,24 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use crate::models::ids::FileID;
+
+#[derive(Serialize)]
+pub struct Version {
+    pub label: String,
+    pub changelog: String,
+    pub latest: bool,
+    pub created: NaiveDateTime,
+}
+
+#[derive(Deserialize)]
+pub struct VersionParams {
+    pub version: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct VersionCreate {
+    pub label: Option<String>,
+    pub changelog: Option<String>,
+    pub description: Option<String>,
+    pub files: Option<Vec<FileID>>,
+}
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use actix_web::web::Data;
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// Notification streams connected to this instance, by user.
+#[derive(Default)]
+pub struct Hub {
+    streams: Mutex<HashMap<i64, Vec<UnboundedSender<()>>>>,
+}
+
+impl Hub {
+    /// Receives a signal whenever the user's notifications change on any instance.
+    pub fn subscribe(&self, user: i64) -> UnboundedReceiver<()> {
+        let (sender, receiver) = unbounded_channel();
+
+        let mut streams = self.streams.lock().unwrap();
+        let senders = streams.entry(user).or_default();
+        senders.retain(|sender| !sender.is_closed());
+        senders.push(sender);
+
+        receiver
+    }
+
+    fn publish(&self, user: i64) {
+        let mut streams = self.streams.lock().unwrap();
+
+        if let Some(senders) = streams.get_mut(&user) {
+            senders.retain(|sender| sender.send(()).is_ok());
+
+            if senders.is_empty() {
+                streams.remove(&user);
+            }
+        }
+    }
+}
+
+/// Forwards the `notifications` channel, fed by a trigger on the notifications table, to local
+/// streams.
+///
+/// Signals sent while the listener reconnects are lost, but a stream catches up on everything
+/// it missed with the next signal it receives.
+pub fn spawn(postgre: PgPool, hub: Data<Hub>) {
+    actix_rt::spawn(async move {
+        loop {
+            if let Err(e) = listen(&postgre, &hub).await {
+                log::error!("failed to listen for notifications: {:?}", e);
+            }
+
+            actix_rt::time::sleep(Duration::from_secs(5)).await;
+        }
+    });
+}
+
+async fn listen(postgre: &PgPool, hub: &Hub) -> Result<(), sqlx::Error> {
+    let mut listener = PgListener::connect_with(postgre).await?;
+    listener.listen("notifications").await?;
+
+    loop {
+        let notification = listener.recv().await?;
+
+        match notification.payload().parse() {
+            Ok(user) => hub.publish(user),
+            Err(_) => log::warn!("invalid notification payload: {}", notification.payload()),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+pub mod live;
+
+use sqlx::PgConnection;
+use crate::models::notifications::{NotificationChannel, NotificationKind};
+
+/// Something that happened which `recipient` may want to hear about.
+pub struct Event {
+    pub recipient: i64,
+    pub kind: NotificationKind,
+    pub actor: Option<i64>,
+    pub structure: Option<i64>,
+    pub comment: Option<i64>,
+}
+
+/// Delivers an event on every channel the recipient hasn't turned it off for.
+///
+/// Runs inside the caller's transaction, so nothing is delivered if the triggering write rolls
+/// back. Users are never notified of their own actions or those of users they muted.
+pub async fn dispatch(event: Event, conn: &mut PgConnection) {
+    if event.actor == Some(event.recipient) {
+        return;
+    }
+
+    if let Some(actor) = event.actor {
+        let muted = sqlx::query_scalar!(
+            r#"SELECT is_muted($1, $2) AS "muted!""#,
+            event.recipient,
+            actor
+        )
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+
+        if muted {
+            return;
+        }
+    }
+
+    for channel in NotificationChannel::ALL {
+        if !is_enabled(event.recipient, event.kind, channel, conn).await {
+            continue;
+        }
+
+        match channel {
+            NotificationChannel::InApp => store(&event, conn).await,
+        }
+    }
+}
+
+/// Dispatches an event to every creator of a structure.
+pub async fn dispatch_to_creators(
+    structure: i64,
+    kind: NotificationKind,
+    actor: Option<i64>,
+    comment: Option<i64>,
+    conn: &mut PgConnection,
+) {
+    let creators = sqlx::query_scalar!(
+        r"SELECT creator FROM structure_creators WHERE structure = $1",
+        structure
+    )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap();
+
+    for creator in creators {
+        dispatch(Event {
+            recipient: creator,
+            kind,
+            actor,
+            structure: Some(structure),
+            comment,
+        }, conn).await;
+    }
+}
+
+pub async fn is_enabled(
+    user: i64,
+    kind: NotificationKind,
+    channel: NotificationChannel,
+    conn: &mut PgConnection,
+) -> bool {
+    sqlx::query_scalar!(
+        r#"SELECT enabled FROM notification_preferences WHERE "user" = $1 AND kind = $2 AND channel = $3"#,
+        user,
+        kind.as_str(),
+        channel.as_str()
+    )
+        .fetch_optional(conn)
+        .await
+        .unwrap()
+        .unwrap_or(true)
+}
+
+async fn store(event: &Event, conn: &mut PgConnection) {
+    sqlx::query!(
+        r#"
+        INSERT INTO notifications ("user", kind, actor, structure, comment)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        event.recipient,
+        event.kind.as_str(),
+        event.actor,
+        event.structure,
+        event.comment
+    )
+        .execute(conn)
+        .await
+        .expect("failed to insert notification");
+}
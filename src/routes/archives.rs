@@ -0,0 +1,105 @@
+use actix_web::{get, post, HttpRequest, HttpResponse, Responder};
+use actix_web::http::header;
+use actix_web::web::{Bytes, Data, Path};
+use sqlx::PgPool;
+use destru::decode_sqids;
+use crate::archives::{export, import, ArchiveError};
+use crate::models::ids::STRUCTURE_FLAG;
+use crate::models::responses::{ImportError, ImportErrorResponse, ImportResponse};
+use crate::routes::auths::{authenticate, is_staff};
+use crate::routes::structures::is_creator;
+
+#[get("/structures/{id}/export")]
+pub async fn export_structure(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let sqid = id.into_inner();
+    let id = match decode_sqids(STRUCTURE_FLAG, &sqid) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if !is_creator(id, user, &mut tx).await && !is_staff(user, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let archive = export(id, &mut tx).await;
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    match archive {
+        Ok(archive) => HttpResponse::Ok()
+            .content_type("application/zip")
+            .insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.zip\"", sqid)))
+            .body(archive),
+        Err(ArchiveError::NotFound) => HttpResponse::NotFound().finish(),
+        Err(e) => {
+            log::error!("failed to export structure: {:?}", e);
+
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[post("/structures/import")]
+pub async fn import_structure(req: HttpRequest, body: Bytes, postgre: Data<PgPool>) -> impl Responder {
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    // Creators are assigned without invitations, so only staff may import.
+    if !is_staff(user, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let imported = match import(body.to_vec(), Some(user), &mut tx).await {
+        Ok(imported) => imported,
+        Err(e) => {
+            let reason = match e {
+                ArchiveError::Zip(_) => ImportError::InvalidArchive,
+                ArchiveError::InvalidManifest => ImportError::InvalidManifest,
+                ArchiveError::UnsupportedVersion(_) => ImportError::UnsupportedVersion,
+                ArchiveError::MissingEntry(_) => ImportError::MissingEntry,
+                ArchiveError::ChecksumMismatch(_) => ImportError::ChecksumMismatch,
+                ArchiveError::InvalidFile(_) => ImportError::InvalidFile,
+                ArchiveError::NoCreators => ImportError::NoCreators,
+                e => {
+                    log::error!("failed to import structure: {:?}", e);
+
+                    return HttpResponse::InternalServerError().finish();
+                }
+            };
+
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::BadRequest().json(ImportErrorResponse { reason });
+        }
+    };
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(ImportResponse {
+        structure: imported.structure,
+        unmatched: imported.unmatched,
+    })
+}
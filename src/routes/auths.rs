@@ -1,6 +1,7 @@
+use std::net::IpAddr;
 use actix_web::{post, HttpRequest, HttpResponse, Responder};
 use actix_web::web::{Data, Json};
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use chrono::{Duration, Utc};
 use actix_web::cookie::time::OffsetDateTime;
 use actix_web::cookie::{Cookie, SameSite};
@@ -12,8 +13,60 @@ use crate::models::responses::{LoginResponse, RegisterErrorResponse, UserError};
 use crate::models::users::{UserLogin, UserRegister};
 
 lazy_static! {
-    static ref NAME_REGEX: Regex = Regex::new(r"^[0-9a-zA-Z_-]{3,100}$").unwrap();
+    pub static ref NAME_REGEX: Regex = Regex::new(r"^[0-9a-zA-Z_-]{3,100}$").unwrap();
     static ref PASSWORD_REGEX: Regex = Regex::new(r"^[0-9a-fA-F]{64}$").unwrap();
+    /// The reverse proxy in front of the server, if any.
+    static ref TRUSTED_PROXY: Option<IpAddr> = dotenvy::var("TRUSTED_PROXY")
+        .ok()
+        .map(|addr| addr.parse().expect("`TRUSTED_PROXY` is not an IP address"));
+}
+
+/// Handles that would collide with routes or impersonate the site.
+const RESERVED_NAMES: [&str; 22] = [
+    "about", "admin", "administrator", "api", "auths", "by", "collections", "destru", "help", "login",
+    "logout", "me", "moderator", "new", "null", "register", "settings", "staff", "structures", "support",
+    "system", "users",
+];
+
+pub fn is_reserved(name: &str) -> bool {
+    RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(name))
+}
+
+/// The address a request came from.
+///
+/// Forwarding headers are only honoured on connections from `TRUSTED_PROXY`, since any other
+/// client can set them to whatever it likes.
+pub fn client_ip(req: &HttpRequest) -> Option<String> {
+    let peer = req.peer_addr()?.ip();
+
+    if Some(peer) != *TRUSTED_PROXY {
+        return Some(peer.to_string());
+    }
+
+    req.connection_info().realip_remote_addr().map(str::to_owned)
+}
+
+pub async fn authenticate(req: &HttpRequest, conn: &mut PgConnection) -> Option<i64> {
+    let cookie = req.cookie("Token")?;
+
+    sqlx::query_scalar!(
+        r#"SELECT "user" FROM user_tokens WHERE token = $1 AND expired > NOW()"#,
+        cookie.value()
+    )
+        .fetch_optional(conn)
+        .await
+        .expect("failed to fetch token")
+}
+
+pub async fn is_staff(user: i64, conn: &mut PgConnection) -> bool {
+    sqlx::query_scalar!(
+        r"SELECT staff FROM users WHERE id = $1",
+        user
+    )
+        .fetch_optional(conn)
+        .await
+        .expect("failed to fetch user")
+        .unwrap_or(false)
 }
 
 #[post("/auths/register")]
@@ -25,6 +78,12 @@ pub async fn register(register: Json<UserRegister>, postgre: Data<PgPool>) -> im
         });
     }
 
+    if is_reserved(&name) {
+        return HttpResponse::BadRequest().json(RegisterErrorResponse {
+            reason: UserError::ReservedName
+        });
+    }
+
     let password = register.password.clone().unwrap_or_default();
     if !PASSWORD_REGEX.is_match(&password) {
         return HttpResponse::BadRequest().json(RegisterErrorResponse {
@@ -34,8 +93,9 @@ pub async fn register(register: Json<UserRegister>, postgre: Data<PgPool>) -> im
 
     let mut tx = postgre.begin().await.unwrap();
 
+    // Names and slugs share one namespace, as in `update_me`.
     let name_exists = sqlx::query_scalar!(
-        r"SELECT EXISTS(SELECT 1 FROM users WHERE name = $1)",
+        r"SELECT EXISTS(SELECT 1 FROM users WHERE name = $1 OR slug = LOWER($1))",
         &name
     )
         .fetch_one(&mut *tx)
@@ -0,0 +1,361 @@
+use actix_web::{delete, get, put, HttpRequest, HttpResponse, Responder};
+use actix_web::web::{Data, Path, Query};
+use sqlx::PgPool;
+use destru::decode_sqids;
+use crate::models::ids::{UserID, USER_FLAG};
+use crate::models::pagination::{Pagination, PaginationParams};
+use crate::models::responses::{BlockError, BlockErrorResponse, UsersResponse};
+use crate::models::users::UserSummary;
+use crate::routes::auths::authenticate;
+
+/// Blocks a user, which also ends any follows and pending invitations between the two.
+#[put("/users/{id}/block")]
+pub async fn block_user(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let id = match decode_sqids(USER_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if user == id {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::BadRequest().json(BlockErrorResponse {
+            reason: BlockError::OwnAccount,
+        });
+    }
+
+    let exists = sqlx::query_scalar!(
+        r"SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)",
+        id
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap()
+        .unwrap();
+
+    if !exists {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::NotFound().finish();
+    }
+
+    sqlx::query!(
+        r"INSERT INTO user_blocks (blocker, blocked) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        user,
+        id
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to insert block");
+
+    sqlx::query!(
+        r"
+        DELETE FROM user_follows
+        WHERE (follower = $1 AND followee = $2) OR (follower = $2 AND followee = $1)
+        ",
+        user,
+        id
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to delete follows");
+
+    sqlx::query!(
+        r"
+        DELETE FROM structure_invitations
+        WHERE (inviter = $1 AND invitee = $2) OR (inviter = $2 AND invitee = $1)
+        ",
+        user,
+        id
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to delete invitations");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
+
+#[delete("/users/{id}/block")]
+pub async fn unblock_user(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let id = match decode_sqids(USER_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let removed = sqlx::query!(
+        r"DELETE FROM user_blocks WHERE blocker = $1 AND blocked = $2",
+        user,
+        id
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to delete block")
+        .rows_affected();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    if removed == 0 {
+        HttpResponse::NotFound().finish()
+    } else {
+        HttpResponse::Ok().finish()
+    }
+}
+
+/// Mutes a user, hiding their content from the caller without them knowing.
+#[put("/users/{id}/mute")]
+pub async fn mute_user(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let id = match decode_sqids(USER_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if user == id {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::BadRequest().json(BlockErrorResponse {
+            reason: BlockError::OwnAccount,
+        });
+    }
+
+    let exists = sqlx::query_scalar!(
+        r"SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)",
+        id
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap()
+        .unwrap();
+
+    if !exists {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::NotFound().finish();
+    }
+
+    sqlx::query!(
+        r"INSERT INTO user_mutes (muter, muted) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        user,
+        id
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to insert mute");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
+
+#[delete("/users/{id}/mute")]
+pub async fn unmute_user(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let id = match decode_sqids(USER_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let removed = sqlx::query!(
+        r"DELETE FROM user_mutes WHERE muter = $1 AND muted = $2",
+        user,
+        id
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to delete mute")
+        .rows_affected();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    if removed == 0 {
+        HttpResponse::NotFound().finish()
+    } else {
+        HttpResponse::Ok().finish()
+    }
+}
+
+#[get("/users/me/blocks")]
+pub async fn get_blocks(req: HttpRequest, params: Query<PaginationParams>, postgre: Data<PgPool>) -> impl Responder {
+    let page = params.page.unwrap_or(1).max(1);
+    let size = params.size.unwrap_or(16).clamp(1, 64);
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let total = sqlx::query_scalar!(
+        r"SELECT COUNT(*) FROM user_blocks WHERE blocker = $1",
+        user
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let users = sqlx::query!(
+        r#"
+        SELECT u.id, u.name, u.avatar, u.slug, u.created, sc.structures AS "structures!"
+        FROM user_blocks b
+        JOIN users u ON u.id = b.blocked
+        LEFT JOIN LATERAL (
+            SELECT COUNT(*) AS structures
+            FROM structure_creators c
+            JOIN structures s ON s.id = c.structure
+            WHERE c.creator = u.id AND s.visibility = 'public' AND s.published <= NOW()
+        ) sc ON true
+        WHERE b.blocker = $3
+        ORDER BY b.created DESC, u.id DESC
+        LIMIT $1
+        OFFSET $2
+        "#,
+        size,
+        (page - 1) * size,
+        user
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|record| UserSummary {
+            id: UserID::from(record.id),
+            name: record.name,
+            avatar: record.avatar,
+            slug: record.slug,
+            structures: record.structures,
+            created: Some(record.created),
+        })
+        .collect();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(UsersResponse {
+        users,
+        pagination: Pagination {
+            page,
+            size,
+            total: (total + size - 1) / size,
+        },
+    })
+}
+
+#[get("/users/me/mutes")]
+pub async fn get_mutes(req: HttpRequest, params: Query<PaginationParams>, postgre: Data<PgPool>) -> impl Responder {
+    let page = params.page.unwrap_or(1).max(1);
+    let size = params.size.unwrap_or(16).clamp(1, 64);
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let total = sqlx::query_scalar!(
+        r"SELECT COUNT(*) FROM user_mutes WHERE muter = $1",
+        user
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let users = sqlx::query!(
+        r#"
+        SELECT u.id, u.name, u.avatar, u.slug, u.created, sc.structures AS "structures!"
+        FROM user_mutes m
+        JOIN users u ON u.id = m.muted
+        LEFT JOIN LATERAL (
+            SELECT COUNT(*) AS structures
+            FROM structure_creators c
+            JOIN structures s ON s.id = c.structure
+            WHERE c.creator = u.id AND s.visibility = 'public' AND s.published <= NOW()
+        ) sc ON true
+        WHERE m.muter = $3
+        ORDER BY m.created DESC, u.id DESC
+        LIMIT $1
+        OFFSET $2
+        "#,
+        size,
+        (page - 1) * size,
+        user
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|record| UserSummary {
+            id: UserID::from(record.id),
+            name: record.name,
+            avatar: record.avatar,
+            slug: record.slug,
+            structures: record.structures,
+            created: Some(record.created),
+        })
+        .collect();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(UsersResponse {
+        users,
+        pagination: Pagination {
+            page,
+            size,
+            total: (total + size - 1) / size,
+        },
+    })
+}
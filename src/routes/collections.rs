@@ -0,0 +1,683 @@
+use std::collections::HashSet;
+use actix_web::{delete, get, patch, post, put, HttpRequest, HttpResponse, Responder};
+use actix_web::web::{Data, Json, Path, Query};
+use sqlx::{PgConnection, PgPool};
+use destru::decode_sqids;
+use crate::models::collections::{Collection, CollectionCreate, CollectionItem, CollectionItemCreate, CollectionItemsOrder, CollectionPreview, CollectionUpdate, CollectionVisibility};
+use crate::models::ids::{CollectionID, StructureID, COLLECTION_FLAG, STRUCTURE_FLAG, USER_FLAG};
+use crate::models::pagination::{Pagination, PaginationParams};
+use crate::models::responses::{CollectionCreateResponse, CollectionError, CollectionErrorResponse, CollectionResponse, CollectionsResponse};
+use crate::models::structures::StructurePreview;
+use crate::models::users::UserPreview;
+use crate::routes::auths::authenticate;
+use crate::routes::policy::{may_view, ProfileSection};
+use crate::routes::structures::is_visible;
+
+const MAX_NAME_LENGTH: usize = 64;
+const MAX_DESCRIPTION_LENGTH: usize = 2000;
+const MAX_NOTE_LENGTH: usize = 500;
+const MAX_ITEMS: i64 = 500;
+
+fn validate_name(name: &str) -> Option<String> {
+    let name = name.trim();
+    let length = name.chars().count();
+
+    if length == 0 || length > MAX_NAME_LENGTH {
+        None
+    } else {
+        Some(name.to_owned())
+    }
+}
+
+fn validate_description(description: &str) -> Option<String> {
+    let description = description.trim();
+
+    if description.chars().count() > MAX_DESCRIPTION_LENGTH {
+        None
+    } else {
+        Some(description.to_owned())
+    }
+}
+
+/// Owner of the collection, if it exists.
+async fn collection_owner(collection: i64, conn: &mut PgConnection) -> Option<i64> {
+    sqlx::query_scalar!(
+        r"SELECT owner FROM collections WHERE id = $1",
+        collection
+    )
+        .fetch_optional(conn)
+        .await
+        .unwrap()
+}
+
+#[get("/collections/{id}")]
+pub async fn get_collection(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let id = match decode_sqids(COLLECTION_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = authenticate(&req, &mut tx).await;
+
+    let row = sqlx::query!(
+        r"
+        SELECT c.owner, c.name, c.description, c.visibility, c.created, c.updated,
+            u.name AS owner_name, u.avatar AS owner_avatar, u.slug AS owner_slug
+        FROM collections c
+        JOIN users u ON u.id = c.owner
+        WHERE c.id = $1
+        ",
+        id
+    )
+        .fetch_optional(&mut *tx)
+        .await
+        .unwrap();
+
+    let record = match row {
+        Some(record) => record,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::NotFound().finish();
+        }
+    };
+
+    let visibility = CollectionVisibility::parse(&record.visibility);
+    if visibility == CollectionVisibility::Private && user != Some(record.owner) {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::NotFound().finish();
+    }
+
+    if !may_view(user, record.owner, ProfileSection::Collections, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::NotFound().finish();
+    }
+
+    // Structures the viewer can't see are left out, as they would be anywhere else.
+    let items = sqlx::query!(
+        r"
+        SELECT
+            s.id,
+            s.name,
+            si.url as image,
+            COALESCE(u.slug, u.name) as creator,
+            s.ratings,
+            s.rating_sum::DOUBLE PRECISION / NULLIF(s.ratings, 0) AS rating,
+            s.rating_score,
+            ci.note,
+            ci.created
+        FROM collection_items ci
+        JOIN structures s ON s.id = ci.structure
+        LEFT JOIN LATERAL (
+            SELECT COALESCE(v.url, i.url) AS url
+            FROM structure_images i
+            LEFT JOIN structure_image_variants v ON v.image = i.id AND v.size = 'card' AND v.format = 'jpeg'
+            WHERE i.structure = s.id
+            ORDER BY i.id
+            LIMIT 1
+        ) si ON true
+        LEFT JOIN LATERAL (
+            SELECT creator
+            FROM structure_creators
+            WHERE structure = s.id
+            ORDER BY position, id
+            LIMIT 1
+        ) sc ON true
+        LEFT JOIN users u ON u.id = sc.creator
+        WHERE ci.collection = $1
+            AND (
+                s.visibility IN ('unlisted', 'public')
+                OR EXISTS(SELECT 1 FROM structure_creators WHERE structure = s.id AND creator = $2)
+            )
+        ORDER BY ci.position, ci.created
+        ",
+        id,
+        user
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|record| CollectionItem {
+            structure: StructurePreview {
+                id: StructureID::from(record.id),
+                name: record.name,
+                image: record.image,
+                creator: record.creator,
+                ratings: record.ratings,
+                rating: record.rating,
+                rating_score: record.rating_score,
+            },
+            note: record.note,
+            added: record.created,
+        })
+        .collect();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(CollectionResponse {
+        collection: Collection {
+            id: CollectionID::from(id),
+            owner: UserPreview {
+                name: record.owner_name,
+                avatar: record.owner_avatar,
+                slug: record.owner_slug,
+            },
+            name: record.name,
+            description: record.description,
+            visibility,
+            items,
+            created: record.created,
+            updated: record.updated,
+        },
+    })
+}
+
+#[get("/users/{id}/collections")]
+pub async fn get_user_collections(
+    req: HttpRequest,
+    id: Path<String>,
+    params: Query<PaginationParams>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(USER_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let page = params.page.unwrap_or(1).max(1);
+    let size = params.size.unwrap_or(16).clamp(1, 64);
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    // Owners see all of their collections, everyone else only the public ones.
+    let viewer = authenticate(&req, &mut tx).await;
+    let own = viewer == Some(id);
+
+    if !may_view(viewer, id, ProfileSection::Collections, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let total = sqlx::query_scalar!(
+        r"SELECT COUNT(*) FROM collections WHERE owner = $1 AND ($2 OR visibility = 'public')",
+        id,
+        own
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let collections = sqlx::query!(
+        r#"
+        SELECT c.id, c.name, c.description, c.visibility, c.updated,
+            (SELECT COUNT(*) FROM collection_items WHERE collection = c.id) AS "items!"
+        FROM collections c
+        WHERE c.owner = $1 AND ($2 OR c.visibility = 'public')
+        ORDER BY c.updated DESC, c.id DESC
+        LIMIT $3
+        OFFSET $4
+        "#,
+        id,
+        own,
+        size,
+        (page - 1) * size
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|record| CollectionPreview {
+            id: CollectionID::from(record.id),
+            name: record.name,
+            description: record.description,
+            visibility: CollectionVisibility::parse(&record.visibility),
+            items: record.items,
+            updated: record.updated,
+        })
+        .collect();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(CollectionsResponse {
+        collections,
+        pagination: Pagination {
+            page,
+            size,
+            total: (total + size - 1) / size,
+        },
+    })
+}
+
+#[post("/collections")]
+pub async fn create_collection(
+    req: HttpRequest,
+    create: Json<CollectionCreate>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let name = match validate_name(create.name.as_deref().unwrap_or_default()) {
+        Some(name) => name,
+        None => return HttpResponse::BadRequest().json(CollectionErrorResponse {
+            reason: CollectionError::InvalidName,
+        }),
+    };
+
+    let description = match validate_description(create.description.as_deref().unwrap_or_default()) {
+        Some(description) => description,
+        None => return HttpResponse::BadRequest().json(CollectionErrorResponse {
+            reason: CollectionError::InvalidDescription,
+        }),
+    };
+
+    let visibility = create.visibility.unwrap_or(CollectionVisibility::Private);
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let collection = sqlx::query_scalar!(
+        r"INSERT INTO collections (owner, name, description, visibility) VALUES ($1, $2, $3, $4) RETURNING id",
+        user,
+        name,
+        description,
+        visibility.as_str()
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .expect("failed to insert collection");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(CollectionCreateResponse {
+        collection: CollectionID::from(collection),
+    })
+}
+
+#[patch("/collections/{id}")]
+pub async fn update_collection(
+    req: HttpRequest,
+    id: Path<String>,
+    update: Json<CollectionUpdate>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(COLLECTION_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let name = match update.name.as_deref().map(validate_name) {
+        Some(None) => return HttpResponse::BadRequest().json(CollectionErrorResponse {
+            reason: CollectionError::InvalidName,
+        }),
+        name => name.flatten(),
+    };
+
+    let description = match update.description.as_deref().map(validate_description) {
+        Some(None) => return HttpResponse::BadRequest().json(CollectionErrorResponse {
+            reason: CollectionError::InvalidDescription,
+        }),
+        description => description.flatten(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    match collection_owner(id, &mut tx).await {
+        Some(owner) if owner == user => {}
+        Some(_) => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Forbidden().finish();
+        }
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::NotFound().finish();
+        }
+    }
+
+    sqlx::query!(
+        r"
+        UPDATE collections
+        SET name = COALESCE($2, name),
+            description = COALESCE($3, description),
+            visibility = COALESCE($4, visibility),
+            updated = NOW()
+        WHERE id = $1
+        ",
+        id,
+        name,
+        description,
+        update.visibility.map(|v| v.as_str())
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to update collection");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
+
+#[delete("/collections/{id}")]
+pub async fn delete_collection(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let id = match decode_sqids(COLLECTION_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    match collection_owner(id, &mut tx).await {
+        Some(owner) if owner == user => {}
+        Some(_) => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Forbidden().finish();
+        }
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::NotFound().finish();
+        }
+    }
+
+    sqlx::query!(
+        r"DELETE FROM collections WHERE id = $1",
+        id
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to delete collection");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
+
+/// Adds a structure to the end of a collection, or updates its note if already there.
+#[post("/collections/{id}/items")]
+pub async fn add_collection_item(
+    req: HttpRequest,
+    id: Path<String>,
+    create: Json<CollectionItemCreate>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(COLLECTION_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let structure = match create.structure {
+        Some(structure) => structure.0,
+        None => return HttpResponse::BadRequest().json(CollectionErrorResponse {
+            reason: CollectionError::StructureNotFound,
+        }),
+    };
+
+    let note = create.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    if note.is_some_and(|n| n.chars().count() > MAX_NOTE_LENGTH) {
+        return HttpResponse::BadRequest().json(CollectionErrorResponse {
+            reason: CollectionError::NoteTooLong,
+        });
+    }
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    match collection_owner(id, &mut tx).await {
+        Some(owner) if owner == user => {}
+        Some(_) => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Forbidden().finish();
+        }
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::NotFound().finish();
+        }
+    }
+
+    if !is_visible(structure, Some(user), &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::BadRequest().json(CollectionErrorResponse {
+            reason: CollectionError::StructureNotFound,
+        });
+    }
+
+    let count = sqlx::query_scalar!(
+        r"SELECT COUNT(*) FROM collection_items WHERE collection = $1 AND structure <> $2",
+        id,
+        structure
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap()
+        .unwrap();
+
+    if count >= MAX_ITEMS {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::BadRequest().json(CollectionErrorResponse {
+            reason: CollectionError::TooManyItems,
+        });
+    }
+
+    sqlx::query!(
+        r"
+        INSERT INTO collection_items (collection, structure, note, position)
+        SELECT $1, $2, $3, COALESCE(MAX(position), 0) + 1 FROM collection_items WHERE collection = $1
+        ON CONFLICT (collection, structure) DO UPDATE SET note = EXCLUDED.note
+        ",
+        id,
+        structure,
+        note
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to insert collection item");
+
+    sqlx::query!(
+        r"UPDATE collections SET updated = NOW() WHERE id = $1",
+        id
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to update collection");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
+
+#[delete("/collections/{id}/items/{structure}")]
+pub async fn remove_collection_item(
+    req: HttpRequest,
+    path: Path<(String, String)>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let (id, structure) = path.into_inner();
+
+    let (id, structure) = match (decode_sqids(COLLECTION_FLAG, &id), decode_sqids(STRUCTURE_FLAG, &structure)) {
+        (Ok(id), Ok(structure)) => (id, structure),
+        _ => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    match collection_owner(id, &mut tx).await {
+        Some(owner) if owner == user => {}
+        Some(_) => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Forbidden().finish();
+        }
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::NotFound().finish();
+        }
+    }
+
+    let removed = sqlx::query!(
+        r"DELETE FROM collection_items WHERE collection = $1 AND structure = $2",
+        id,
+        structure
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to delete collection item")
+        .rows_affected();
+
+    if removed > 0 {
+        sqlx::query!(
+            r"UPDATE collections SET updated = NOW() WHERE id = $1",
+            id
+        )
+            .execute(&mut *tx)
+            .await
+            .expect("failed to update collection");
+    }
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    if removed == 0 {
+        HttpResponse::NotFound().finish()
+    } else {
+        HttpResponse::Ok().finish()
+    }
+}
+
+#[put("/collections/{id}/items")]
+pub async fn order_collection_items(
+    req: HttpRequest,
+    id: Path<String>,
+    order: Json<CollectionItemsOrder>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(COLLECTION_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    match collection_owner(id, &mut tx).await {
+        Some(owner) if owner == user => {}
+        Some(_) => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Forbidden().finish();
+        }
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::NotFound().finish();
+        }
+    }
+
+    let current: HashSet<i64> = sqlx::query_scalar!(
+        r"SELECT structure FROM collection_items WHERE collection = $1",
+        id
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap()
+        .into_iter()
+        .collect();
+
+    let structures: Vec<i64> = order.structures.iter().map(|s| s.0).collect();
+
+    // The new order has to name every item exactly once.
+    if structures.len() != current.len() || structures.iter().collect::<HashSet<_>>() != current.iter().collect() {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::BadRequest().json(CollectionErrorResponse {
+            reason: CollectionError::InvalidItems,
+        });
+    }
+
+    sqlx::query!(
+        r"
+        UPDATE collection_items ci
+        SET position = o.position::INTEGER
+        FROM UNNEST($2::BIGINT[]) WITH ORDINALITY AS o(structure, position)
+        WHERE ci.collection = $1 AND ci.structure = o.structure
+        ",
+        id,
+        &structures
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to order collection items");
+
+    sqlx::query!(
+        r"UPDATE collections SET updated = NOW() WHERE id = $1",
+        id
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to update collection");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
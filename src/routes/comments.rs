@@ -0,0 +1,431 @@
+use std::collections::HashMap;
+use actix_web::{delete, get, patch, post, HttpRequest, HttpResponse, Responder};
+use actix_web::web::{Data, Json, Path, Query};
+use sqlx::PgPool;
+use destru::decode_sqids;
+use crate::markdown;
+use crate::models::comments::{Comment, CommentCreate, CommentEdit, DbComment};
+use crate::models::notifications::NotificationKind;
+use crate::notifications::{self, Event};
+use crate::models::ids::{CommentID, COMMENT_FLAG, STRUCTURE_FLAG};
+use crate::models::pagination::{CursorPagination, CursorParams};
+use crate::models::responses::{BlockError, BlockErrorResponse, CommentError, CommentErrorResponse, CommentResponse, CommentsResponse};
+use crate::routes::auths::{authenticate, is_staff};
+use crate::routes::policy::{may_interact, Target};
+use crate::routes::structures::is_visible;
+
+const MAX_BODY_LENGTH: usize = 10000;
+
+fn validate_body(body: &Option<String>) -> Option<String> {
+    let body = body.as_deref().unwrap_or_default().trim();
+    let length = body.chars().count();
+
+    if length == 0 || length > MAX_BODY_LENGTH {
+        None
+    } else {
+        Some(body.to_owned())
+    }
+}
+
+#[get("/structures/{id}/comments")]
+pub async fn get_comments(
+    req: HttpRequest,
+    id: Path<String>,
+    params: Query<CursorParams<CommentID>>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+    let size = params.size.unwrap_or(16).clamp(1, 64);
+    let cursor = params.cursor.map(|c| c.0);
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = authenticate(&req, &mut tx).await;
+
+    if !is_visible(id, user, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::NotFound().finish();
+    }
+
+    let mut roots = sqlx::query_as!(
+        DbComment,
+        r"
+        SELECT
+            c.id,
+            c.parent,
+            u.name AS author_name,
+            u.avatar AS author_avatar,
+            u.slug AS author_slug,
+            c.body,
+            c.body_html,
+            c.removed,
+            c.created,
+            c.edited
+        FROM structure_comments c
+        JOIN users u ON u.id = c.author
+        WHERE c.structure = $1
+            AND c.parent IS NULL
+            AND ($2::BIGINT IS NULL OR c.id < $2)
+            AND ($4::BIGINT IS NULL OR NOT is_muted($4, c.author))
+        ORDER BY c.id DESC
+        LIMIT $3
+        ",
+        id,
+        cursor,
+        size + 1,
+        user,
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap();
+
+    let next = if roots.len() as i64 > size {
+        roots.truncate(size as usize);
+        roots.last().map(|c| CommentID::from(c.id))
+    } else {
+        None
+    };
+
+    let ids: Vec<i64> = roots.iter().map(|c| c.id).collect();
+
+    let replies = sqlx::query_as!(
+        DbComment,
+        r"
+        SELECT
+            c.id,
+            c.parent,
+            u.name AS author_name,
+            u.avatar AS author_avatar,
+            u.slug AS author_slug,
+            c.body,
+            c.body_html,
+            c.removed,
+            c.created,
+            c.edited
+        FROM structure_comments c
+        JOIN users u ON u.id = c.author
+        WHERE c.parent = ANY($1)
+            AND ($2::BIGINT IS NULL OR NOT is_muted($2, c.author))
+        ORDER BY c.id
+        ",
+        &ids,
+        user,
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    let mut grouped: HashMap<i64, Vec<Comment>> = HashMap::new();
+    for reply in replies {
+        if let Some(parent) = reply.parent {
+            grouped.entry(parent).or_default().push(Comment::from(reply));
+        }
+    }
+
+    let comments = roots
+        .into_iter()
+        .map(|root| {
+            let replies = grouped.remove(&root.id).unwrap_or_default();
+            let mut comment = Comment::from(root);
+            comment.replies = replies;
+            comment
+        })
+        .collect();
+
+    HttpResponse::Ok().json(CommentsResponse {
+        comments,
+        pagination: CursorPagination { next, size },
+    })
+}
+
+async fn get_comment_response(id: i64, postgre: &PgPool) -> HttpResponse {
+    let comment = sqlx::query_as!(
+        DbComment,
+        r"
+        SELECT
+            c.id,
+            c.parent,
+            u.name AS author_name,
+            u.avatar AS author_avatar,
+            u.slug AS author_slug,
+            c.body,
+            c.body_html,
+            c.removed,
+            c.created,
+            c.edited
+        FROM structure_comments c
+        JOIN users u ON u.id = c.author
+        WHERE c.id = $1
+        ",
+        id,
+    )
+        .fetch_one(postgre)
+        .await
+        .unwrap();
+
+    HttpResponse::Ok().json(CommentResponse {
+        comment: Comment::from(comment),
+    })
+}
+
+#[post("/structures/{id}/comments")]
+pub async fn create_comment(
+    req: HttpRequest,
+    id: Path<String>,
+    create: Json<CommentCreate>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let body = match validate_body(&create.body) {
+        Some(body) => body,
+        None => return HttpResponse::BadRequest().json(CommentErrorResponse {
+            reason: CommentError::InvalidBody,
+        }),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if !is_visible(id, Some(user), &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::NotFound().finish();
+    }
+
+    if !may_interact(user, Target::Structure(id), &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().json(BlockErrorResponse {
+            reason: BlockError::Blocked,
+        });
+    }
+
+    let parent = create.parent.map(|p| p.0);
+
+    if let Some(parent) = parent {
+        let grandparent = sqlx::query_scalar!(
+            r"SELECT parent FROM structure_comments WHERE id = $1 AND structure = $2",
+            parent,
+            id
+        )
+            .fetch_optional(&mut *tx)
+            .await
+            .unwrap();
+
+        // Replies are only one level deep, so a reply can't be replied to.
+        if !matches!(grandparent, Some(None)) {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::BadRequest().json(CommentErrorResponse {
+                reason: CommentError::InvalidParent,
+            });
+        }
+
+        if !may_interact(user, Target::Comment(parent), &mut tx).await {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Forbidden().json(BlockErrorResponse {
+                reason: BlockError::Blocked,
+            });
+        }
+    }
+
+    let comment = sqlx::query_scalar!(
+        r"
+        INSERT INTO structure_comments (structure, parent, author, body, body_html)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        ",
+        id,
+        parent,
+        user,
+        &body,
+        markdown::render(&body)
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .expect("failed to insert comment");
+
+    match parent {
+        Some(parent) => {
+            let author = sqlx::query_scalar!(
+                r"SELECT author FROM structure_comments WHERE id = $1",
+                parent
+            )
+                .fetch_one(&mut *tx)
+                .await
+                .unwrap();
+
+            notifications::dispatch(Event {
+                recipient: author,
+                kind: NotificationKind::Reply,
+                actor: Some(user),
+                structure: Some(id),
+                comment: Some(comment),
+            }, &mut tx).await;
+        }
+        None => notifications::dispatch_to_creators(id, NotificationKind::Comment, Some(user), Some(comment), &mut tx).await,
+    }
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    get_comment_response(comment, &postgre).await
+}
+
+#[patch("/comments/{id}")]
+pub async fn edit_comment(
+    req: HttpRequest,
+    id: Path<String>,
+    edit: Json<CommentEdit>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(COMMENT_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let body = match validate_body(&edit.body) {
+        Some(body) => body,
+        None => return HttpResponse::BadRequest().json(CommentErrorResponse {
+            reason: CommentError::InvalidBody,
+        }),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let row = sqlx::query!(
+        r"SELECT author, structure, removed FROM structure_comments WHERE id = $1",
+        id
+    )
+        .fetch_optional(&mut *tx)
+        .await
+        .unwrap();
+
+    match row {
+        Some(record) if record.removed.is_none() => {
+            // Comments on structures that have since gone private are frozen, as for new ones.
+            if !is_visible(record.structure, Some(user), &mut tx).await {
+                tx.commit().await.expect("failed to commit transaction");
+
+                return HttpResponse::NotFound().finish();
+            }
+
+            if record.author != user {
+                tx.commit().await.expect("failed to commit transaction");
+
+                return HttpResponse::Forbidden().finish();
+            }
+        }
+        _ => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::NotFound().finish();
+        }
+    }
+
+    sqlx::query!(
+        r"UPDATE structure_comments SET body = $2, body_html = $3, edited = NOW() WHERE id = $1",
+        id,
+        body,
+        markdown::render(&body)
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to update comment");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    get_comment_response(id, &postgre).await
+}
+
+#[delete("/comments/{id}")]
+pub async fn delete_comment(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let id = match decode_sqids(COMMENT_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let comment = sqlx::query!(
+        r"SELECT author, structure FROM structure_comments WHERE id = $1 AND removed IS NULL",
+        id
+    )
+        .fetch_optional(&mut *tx)
+        .await
+        .unwrap();
+
+    let removed = match &comment {
+        Some(comment) if comment.author == user => "author",
+        Some(_) if is_staff(user, &mut tx).await => "moderator",
+        Some(_) => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Forbidden().finish();
+        }
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::NotFound().finish();
+        }
+    };
+
+    sqlx::query!(
+        r"UPDATE structure_comments SET removed = $2 WHERE id = $1",
+        id,
+        removed
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to remove comment");
+
+    if let Some(comment) = comment.filter(|_| removed == "moderator") {
+        notifications::dispatch(Event {
+            recipient: comment.author,
+            kind: NotificationKind::Moderation,
+            actor: Some(user),
+            structure: Some(comment.structure),
+            comment: Some(id),
+        }, &mut tx).await;
+    }
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
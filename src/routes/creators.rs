@@ -0,0 +1,574 @@
+use std::collections::HashSet;
+use actix_web::{delete, get, post, put, HttpRequest, HttpResponse, Responder};
+use actix_web::web::{Data, Json, Path};
+use sqlx::PgPool;
+use destru::decode_sqids;
+use crate::models::creators::{CreatorRole, CreatorUpdate, CreatorsOrder, Invitation, InvitationCreate, OwnershipTransfer};
+use crate::models::ids::{StructureID, STRUCTURE_FLAG, USER_FLAG};
+use crate::models::notifications::NotificationKind;
+use crate::models::responses::{BlockError, BlockErrorResponse, CreatorError, CreatorErrorResponse, InvitationsResponse};
+use crate::models::users::UserPreview;
+use crate::notifications::{self, Event};
+use crate::routes::auths::authenticate;
+use crate::routes::policy::{may_interact, Target};
+use crate::routes::structures::creator_role;
+
+#[post("/structures/{id}/invitations")]
+pub async fn invite_creator(
+    req: HttpRequest,
+    id: Path<String>,
+    invite: Json<InvitationCreate>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let invitee = match invite.user {
+        Some(user) => user.0,
+        None => return HttpResponse::BadRequest().json(CreatorErrorResponse {
+            reason: CreatorError::InvalidUser,
+        }),
+    };
+
+    let role = invite.role.unwrap_or(CreatorRole::Contributor);
+    if role == CreatorRole::Owner {
+        return HttpResponse::BadRequest().json(CreatorErrorResponse {
+            reason: CreatorError::InvalidRole,
+        });
+    }
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    // Creators can only hand out roles up to their own.
+    match creator_role(id, user, &mut tx).await {
+        Some(own) if own.rank() <= role.rank() => {}
+        Some(_) => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::BadRequest().json(CreatorErrorResponse {
+                reason: CreatorError::InvalidRole,
+            });
+        }
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Forbidden().finish();
+        }
+    }
+
+    let invitee_exists = sqlx::query_scalar!(
+        r"SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)",
+        invitee
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap()
+        .unwrap();
+
+    if !invitee_exists {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::BadRequest().json(CreatorErrorResponse {
+            reason: CreatorError::InvalidUser,
+        });
+    }
+
+    if !may_interact(user, Target::User(invitee), &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().json(BlockErrorResponse {
+            reason: BlockError::Blocked,
+        });
+    }
+
+    if creator_role(id, invitee, &mut tx).await.is_some() {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::BadRequest().json(CreatorErrorResponse {
+            reason: CreatorError::AlreadyCreator,
+        });
+    }
+
+    sqlx::query!(
+        r"
+        INSERT INTO structure_invitations (structure, inviter, invitee, role)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (structure, invitee)
+        DO UPDATE SET inviter = $2, role = $4, created = NOW()
+        ",
+        id,
+        user,
+        invitee,
+        role.as_str()
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to insert invitation");
+
+    notifications::dispatch(Event {
+        recipient: invitee,
+        kind: NotificationKind::Invitation,
+        actor: Some(user),
+        structure: Some(id),
+        comment: None,
+    }, &mut tx).await;
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
+
+#[get("/invitations")]
+pub async fn get_invitations(req: HttpRequest, postgre: Data<PgPool>) -> impl Responder {
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let invitations = sqlx::query!(
+        r"
+        SELECT i.structure, s.name, u.name AS inviter_name, u.avatar AS inviter_avatar, u.slug AS inviter_slug, i.role, i.created
+        FROM structure_invitations i
+        JOIN structures s ON s.id = i.structure
+        JOIN users u ON u.id = i.inviter
+        WHERE i.invitee = $1
+        ORDER BY i.id DESC
+        ",
+        user
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|record| Invitation {
+            structure: StructureID::from(record.structure),
+            name: record.name,
+            inviter: UserPreview {
+                name: record.inviter_name,
+                avatar: record.inviter_avatar,
+                slug: record.inviter_slug,
+            },
+            role: CreatorRole::parse(&record.role),
+            created: record.created,
+        })
+        .collect();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(InvitationsResponse { invitations })
+}
+
+#[post("/structures/{id}/invitations/accept")]
+pub async fn accept_invitation(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let role = sqlx::query_scalar!(
+        r"DELETE FROM structure_invitations WHERE structure = $1 AND invitee = $2 RETURNING role",
+        id,
+        user
+    )
+        .fetch_optional(&mut *tx)
+        .await
+        .expect("failed to delete invitation");
+
+    let role = match role {
+        Some(role) => role,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::NotFound().finish();
+        }
+    };
+
+    sqlx::query!(
+        r"
+        INSERT INTO structure_creators (structure, creator, role, position)
+        SELECT $1, $2, $3, COALESCE(MAX(position), 0) + 1
+        FROM structure_creators
+        WHERE structure = $1
+        ON CONFLICT (structure, creator) DO NOTHING
+        ",
+        id,
+        user,
+        role
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to insert creator");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
+
+#[post("/structures/{id}/invitations/decline")]
+pub async fn decline_invitation(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let deleted = sqlx::query!(
+        r"DELETE FROM structure_invitations WHERE structure = $1 AND invitee = $2",
+        id,
+        user
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to delete invitation")
+        .rows_affected();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    if deleted == 0 {
+        HttpResponse::NotFound().finish()
+    } else {
+        HttpResponse::Ok().finish()
+    }
+}
+
+#[put("/structures/{id}/creators/{user}")]
+pub async fn update_creator(
+    req: HttpRequest,
+    path: Path<(String, String)>,
+    update: Json<CreatorUpdate>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let (id, target) = path.into_inner();
+    let (id, target) = match (decode_sqids(STRUCTURE_FLAG, &id), decode_sqids(USER_FLAG, &target)) {
+        (Ok(id), Ok(target)) => (id, target),
+        _ => return HttpResponse::NotFound().finish(),
+    };
+
+    let role = match update.role {
+        Some(role) if role != CreatorRole::Owner => role,
+        _ => return HttpResponse::BadRequest().json(CreatorErrorResponse {
+            reason: CreatorError::InvalidRole,
+        }),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if creator_role(id, user, &mut tx).await != Some(CreatorRole::Owner) {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().finish();
+    }
+
+    match creator_role(id, target, &mut tx).await {
+        Some(CreatorRole::Owner) => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::BadRequest().json(CreatorErrorResponse {
+                reason: CreatorError::InvalidRole,
+            });
+        }
+        Some(_) => {}
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::BadRequest().json(CreatorErrorResponse {
+                reason: CreatorError::NotCreator,
+            });
+        }
+    }
+
+    sqlx::query!(
+        r"UPDATE structure_creators SET role = $3 WHERE structure = $1 AND creator = $2",
+        id,
+        target,
+        role.as_str()
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to update creator");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
+
+#[delete("/structures/{id}/creators/{user}")]
+pub async fn remove_creator(
+    req: HttpRequest,
+    path: Path<(String, String)>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let (id, target) = path.into_inner();
+    let (id, target) = match (decode_sqids(STRUCTURE_FLAG, &id), decode_sqids(USER_FLAG, &target)) {
+        (Ok(id), Ok(target)) => (id, target),
+        _ => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if creator_role(id, user, &mut tx).await != Some(CreatorRole::Owner) {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let deleted = sqlx::query!(
+        r"DELETE FROM structure_creators WHERE structure = $1 AND creator = $2 AND role <> 'owner'",
+        id,
+        target
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to delete creator")
+        .rows_affected();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    if deleted == 0 {
+        HttpResponse::BadRequest().json(CreatorErrorResponse {
+            reason: CreatorError::NotCreator,
+        })
+    } else {
+        HttpResponse::Ok().finish()
+    }
+}
+
+#[put("/structures/{id}/creators")]
+pub async fn order_creators(
+    req: HttpRequest,
+    id: Path<String>,
+    order: Json<CreatorsOrder>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    match creator_role(id, user, &mut tx).await {
+        Some(CreatorRole::Owner | CreatorRole::Maintainer) => {}
+        _ => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Forbidden().finish();
+        }
+    }
+
+    let current: HashSet<i64> = sqlx::query_scalar!(
+        r"SELECT creator FROM structure_creators WHERE structure = $1",
+        id
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap()
+        .into_iter()
+        .collect();
+
+    let creators: Vec<i64> = order.creators.iter().map(|c| c.0).collect();
+
+    // The new order has to name every creator exactly once.
+    if creators.len() != current.len() || creators.iter().collect::<HashSet<_>>() != current.iter().collect() {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::BadRequest().json(CreatorErrorResponse {
+            reason: CreatorError::InvalidOrder,
+        });
+    }
+
+    sqlx::query!(
+        r"
+        UPDATE structure_creators c
+        SET position = o.position::INTEGER
+        FROM UNNEST($2::BIGINT[]) WITH ORDINALITY AS o(creator, position)
+        WHERE c.structure = $1 AND c.creator = o.creator
+        ",
+        id,
+        &creators
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to order creators");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
+
+#[post("/structures/{id}/transfer")]
+pub async fn transfer_ownership(
+    req: HttpRequest,
+    id: Path<String>,
+    transfer: Json<OwnershipTransfer>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let target = match transfer.user {
+        Some(user) => user.0,
+        None => return HttpResponse::BadRequest().json(CreatorErrorResponse {
+            reason: CreatorError::InvalidUser,
+        }),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if creator_role(id, user, &mut tx).await != Some(CreatorRole::Owner) {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().finish();
+    }
+
+    if target == user || creator_role(id, target, &mut tx).await.is_none() {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::BadRequest().json(CreatorErrorResponse {
+            reason: CreatorError::NotCreator,
+        });
+    }
+
+    // Demote first, only one owner is allowed at a time.
+    sqlx::query!(
+        r"UPDATE structure_creators SET role = 'maintainer' WHERE structure = $1 AND creator = $2",
+        id,
+        user
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to demote owner");
+
+    sqlx::query!(
+        r"UPDATE structure_creators SET role = 'owner' WHERE structure = $1 AND creator = $2",
+        id,
+        target
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to promote owner");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
+
+#[post("/structures/{id}/leave")]
+pub async fn leave_structure(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let reason = match creator_role(id, user, &mut tx).await {
+        Some(CreatorRole::Owner) => Some(CreatorError::OwnerCannotLeave),
+        Some(_) => None,
+        None => Some(CreatorError::NotCreator),
+    };
+
+    if let Some(reason) = reason {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::BadRequest().json(CreatorErrorResponse { reason });
+    }
+
+    sqlx::query!(
+        r"DELETE FROM structure_creators WHERE structure = $1 AND creator = $2",
+        id,
+        user
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to delete creator");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
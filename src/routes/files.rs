@@ -0,0 +1,138 @@
+use actix_web::{post, HttpRequest, HttpResponse, Responder};
+use actix_web::web::{Bytes, Data, Path, Query};
+use lazy_static::lazy_static;
+use regex::Regex;
+use sqlx::PgPool;
+use destru::decode_sqids;
+use crate::formats::{FormatError, REGISTRY};
+use crate::models::files::FileUpload;
+use crate::models::ids::STRUCTURE_FLAG;
+use crate::models::responses::{FileError, FileErrorResponse};
+use crate::routes::auths::authenticate;
+use crate::routes::structures::is_maintainer;
+use crate::routes::versions::{insert_version, is_valid_label};
+use crate::storage::blobs;
+
+lazy_static! {
+    pub static ref FILE_NAME_REGEX: Regex = Regex::new(r"^[0-9a-zA-Z_-][0-9a-zA-Z._-]{0,127}$").unwrap();
+}
+
+const MAX_FILE_SIZE: usize = 32 * 1024 * 1024;
+
+#[post("/structures/{id}/files")]
+pub async fn upload_file(
+    req: HttpRequest,
+    id: Path<String>,
+    upload: Query<FileUpload>,
+    body: Bytes,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    if body.len() > MAX_FILE_SIZE {
+        return HttpResponse::PayloadTooLarge().json(FileErrorResponse {
+            reason: FileError::TooLarge,
+        });
+    }
+
+    let name = upload.name.clone().unwrap_or_default();
+    if !FILE_NAME_REGEX.is_match(&name) {
+        return HttpResponse::BadRequest().json(FileErrorResponse {
+            reason: FileError::InvalidName,
+        });
+    }
+
+    let label = upload.version.clone().unwrap_or_default();
+    if !is_valid_label(&label) {
+        return HttpResponse::BadRequest().json(FileErrorResponse {
+            reason: FileError::InvalidVersion,
+        });
+    }
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if !is_maintainer(id, user, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let parsed = {
+        let name = name.clone();
+        let body = body.clone();
+        actix_rt::task::spawn_blocking(move || REGISTRY.parse(&name, &body))
+            .await
+            .expect("failed to parse file")
+    };
+
+    let (format, metadata) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::BadRequest().json(FileErrorResponse {
+                reason: match e {
+                    FormatError::Unsupported => FileError::UnsupportedFormat,
+                    FormatError::Mismatch => FileError::FormatMismatch,
+                    FormatError::Corrupt(_) => FileError::CorruptFile,
+                },
+            });
+        }
+    };
+
+    let changelog = upload.changelog.clone().unwrap_or_default();
+
+    // Every upload lands in a fresh version alongside the files of the previous one.
+    let version = match insert_version(id, &label, &changelog, None, None, &mut tx).await {
+        Some(version) => version,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::BadRequest().json(FileErrorResponse {
+                reason: FileError::VersionExists,
+            });
+        }
+    };
+
+    let blob = match blobs::store(body.to_vec(), &mut tx).await {
+        Ok(blob) => blob,
+        Err(e) => {
+            log::error!("failed to store structure file: {:?}", e);
+
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    sqlx::query!(
+        r"
+        INSERT INTO structure_files (structure, version, name, url, blob, format, metadata, created)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+        ",
+        id,
+        version,
+        name,
+        blob.url,
+        blob.hash,
+        format,
+        serde_json::to_value(metadata).unwrap()
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to insert file");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
@@ -0,0 +1,427 @@
+use actix_web::{delete, get, put, HttpRequest, HttpResponse, Responder};
+use actix_web::web::{Data, Path, Query};
+use sqlx::PgPool;
+use destru::decode_sqids;
+use crate::models::feed::{FeedActivity, FeedCursor, FeedItem};
+use crate::models::ids::{StructureID, UserID, USER_FLAG};
+use crate::models::notifications::NotificationKind;
+use crate::models::pagination::{CursorPagination, CursorParams, Pagination, PaginationParams};
+use crate::models::responses::{BlockError, BlockErrorResponse, FeedResponse, FollowError, FollowErrorResponse, UsersResponse};
+use crate::models::structures::StructurePreview;
+use crate::models::users::UserSummary;
+use crate::notifications::{self, Event};
+use crate::routes::auths::{authenticate, is_staff};
+use crate::routes::policy::{may_interact, may_view, ProfileSection, Target};
+
+#[put("/users/{id}/follow")]
+pub async fn follow_user(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let id = match decode_sqids(USER_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if user == id {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::BadRequest().json(FollowErrorResponse {
+            reason: FollowError::OwnAccount,
+        });
+    }
+
+    let exists = sqlx::query_scalar!(
+        r"SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)",
+        id
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap()
+        .unwrap();
+
+    if !exists {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::NotFound().finish();
+    }
+
+    if !may_interact(user, Target::User(id), &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().json(BlockErrorResponse {
+            reason: BlockError::Blocked,
+        });
+    }
+
+    let inserted = sqlx::query!(
+        r"INSERT INTO user_follows (follower, followee) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        user,
+        id
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to insert follow")
+        .rows_affected();
+
+    if inserted > 0 {
+        notifications::dispatch(Event {
+            recipient: id,
+            kind: NotificationKind::Follow,
+            actor: Some(user),
+            structure: None,
+            comment: None,
+        }, &mut tx).await;
+    }
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
+
+#[delete("/users/{id}/follow")]
+pub async fn unfollow_user(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let id = match decode_sqids(USER_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let removed = sqlx::query!(
+        r"DELETE FROM user_follows WHERE follower = $1 AND followee = $2",
+        user,
+        id
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to delete follow")
+        .rows_affected();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    if removed == 0 {
+        HttpResponse::NotFound().finish()
+    } else {
+        HttpResponse::Ok().finish()
+    }
+}
+
+#[get("/users/{id}/followers")]
+pub async fn get_followers(
+    req: HttpRequest,
+    id: Path<String>,
+    params: Query<PaginationParams>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(USER_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let page = params.page.unwrap_or(1).max(1);
+    let size = params.size.unwrap_or(16).clamp(1, 64);
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let viewer = authenticate(&req, &mut tx).await;
+
+    if !may_view(viewer, id, ProfileSection::Followers, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let staff = match viewer {
+        Some(viewer) => is_staff(viewer, &mut tx).await,
+        None => false,
+    };
+
+    let total = sqlx::query_scalar!(
+        r"SELECT COUNT(*) FROM user_follows WHERE followee = $1",
+        id
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let users = sqlx::query!(
+        r#"
+        SELECT u.id, u.name, u.avatar, u.slug, u.private, u.created, sc.structures AS "structures!"
+        FROM user_follows f
+        JOIN users u ON u.id = f.follower
+        LEFT JOIN LATERAL (
+            SELECT COUNT(*) AS structures
+            FROM structure_creators c
+            JOIN structures s ON s.id = c.structure
+            WHERE c.creator = u.id AND s.visibility = 'public' AND s.published <= NOW()
+        ) sc ON true
+        WHERE f.followee = $3
+        ORDER BY f.created DESC, u.id DESC
+        LIMIT $1
+        OFFSET $2
+        "#,
+        size,
+        (page - 1) * size,
+        id
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|record| {
+            let summary = UserSummary {
+                id: UserID::from(record.id),
+                name: record.name,
+                avatar: record.avatar,
+                slug: record.slug,
+                structures: record.structures,
+                created: Some(record.created),
+            };
+
+            // Private users are listed by name only, as on their profile.
+            if record.private && !staff && viewer != Some(record.id) {
+                summary.into_private()
+            } else {
+                summary
+            }
+        })
+        .collect();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(UsersResponse {
+        users,
+        pagination: Pagination {
+            page,
+            size,
+            total: (total + size - 1) / size,
+        },
+    })
+}
+
+#[get("/users/{id}/following")]
+pub async fn get_following(
+    req: HttpRequest,
+    id: Path<String>,
+    params: Query<PaginationParams>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(USER_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let page = params.page.unwrap_or(1).max(1);
+    let size = params.size.unwrap_or(16).clamp(1, 64);
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let viewer = authenticate(&req, &mut tx).await;
+
+    if !may_view(viewer, id, ProfileSection::Activity, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let staff = match viewer {
+        Some(viewer) => is_staff(viewer, &mut tx).await,
+        None => false,
+    };
+
+    let total = sqlx::query_scalar!(
+        r"SELECT COUNT(*) FROM user_follows WHERE follower = $1",
+        id
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let users = sqlx::query!(
+        r#"
+        SELECT u.id, u.name, u.avatar, u.slug, u.private, u.created, sc.structures AS "structures!"
+        FROM user_follows f
+        JOIN users u ON u.id = f.followee
+        LEFT JOIN LATERAL (
+            SELECT COUNT(*) AS structures
+            FROM structure_creators c
+            JOIN structures s ON s.id = c.structure
+            WHERE c.creator = u.id AND s.visibility = 'public' AND s.published <= NOW()
+        ) sc ON true
+        WHERE f.follower = $3
+        ORDER BY f.created DESC, u.id DESC
+        LIMIT $1
+        OFFSET $2
+        "#,
+        size,
+        (page - 1) * size,
+        id
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|record| {
+            let summary = UserSummary {
+                id: UserID::from(record.id),
+                name: record.name,
+                avatar: record.avatar,
+                slug: record.slug,
+                structures: record.structures,
+                created: Some(record.created),
+            };
+
+            // Private users are listed by name only, as on their profile.
+            if record.private && !staff && viewer != Some(record.id) {
+                summary.into_private()
+            } else {
+                summary
+            }
+        })
+        .collect();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(UsersResponse {
+        users,
+        pagination: Pagination {
+            page,
+            size,
+            total: (total + size - 1) / size,
+        },
+    })
+}
+
+/// Public structures by followed creators, newest activity first.
+///
+/// Each structure appears once, at the later of its publication and its latest version. The
+/// feed is assembled at read time from the follow graph rather than stored per follower, and
+/// leaves out structures co-created by anyone the caller muted.
+#[get("/feed")]
+pub async fn get_feed(
+    req: HttpRequest,
+    params: Query<CursorParams<FeedCursor>>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let size = params.size.unwrap_or(16).clamp(1, 64);
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let mut items: Vec<FeedItem> = sqlx::query!(
+        r#"
+        SELECT
+            s.id,
+            s.name,
+            si.url as image,
+            COALESCE(u.slug, u.name) as creator,
+            s.ratings,
+            s.rating_sum::DOUBLE PRECISION / NULLIF(s.ratings, 0) AS rating,
+            s.rating_score,
+            lv.label AS "version?",
+            a.time AS "time!",
+            COALESCE(lv.created > s.published, false) AS "updated!"
+        FROM (
+            SELECT DISTINCT c.structure
+            FROM user_follows f
+            JOIN structure_creators c ON c.creator = f.followee
+            WHERE f.follower = $1
+        ) fs
+        JOIN structures s ON s.id = fs.structure
+        LEFT JOIN structure_versions lv ON lv.id = s.latest
+        CROSS JOIN LATERAL (
+            SELECT GREATEST(s.published, lv.created) AS time
+        ) a
+        LEFT JOIN LATERAL (
+            SELECT COALESCE(v.url, i.url) AS url
+            FROM structure_images i
+            LEFT JOIN structure_image_variants v ON v.image = i.id AND v.size = 'card' AND v.format = 'jpeg'
+            WHERE i.structure = s.id
+            ORDER BY i.id
+            LIMIT 1
+        ) si ON true
+        LEFT JOIN LATERAL (
+            SELECT creator
+            FROM structure_creators
+            WHERE structure = s.id
+            ORDER BY position, id
+            LIMIT 1
+        ) sc ON true
+        LEFT JOIN users u ON u.id = sc.creator
+        WHERE s.visibility = 'public'
+            AND s.published <= NOW()
+            AND NOT EXISTS(SELECT 1 FROM structure_creators mc WHERE mc.structure = s.id AND is_muted($1, mc.creator))
+            AND ($2::TIMESTAMP IS NULL OR (a.time, s.id) < ($2, $3))
+        ORDER BY a.time DESC, s.id DESC
+        LIMIT $4
+        "#,
+        user,
+        params.cursor.map(|c| c.time),
+        params.cursor.map(|c| c.structure.0),
+        size + 1,
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|record| FeedItem {
+            structure: StructurePreview {
+                id: StructureID::from(record.id),
+                name: record.name,
+                image: record.image,
+                creator: record.creator,
+                ratings: record.ratings,
+                rating: record.rating,
+                rating_score: record.rating_score,
+            },
+            activity: if record.updated { FeedActivity::Updated } else { FeedActivity::Published },
+            version: record.version,
+            time: record.time,
+        })
+        .collect();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    let next = if items.len() as i64 > size {
+        items.truncate(size as usize);
+        items.last().map(|item| FeedCursor {
+            time: item.time,
+            structure: item.structure.id,
+        })
+    } else {
+        None
+    };
+
+    HttpResponse::Ok().json(FeedResponse {
+        items,
+        pagination: CursorPagination { next, size },
+    })
+}
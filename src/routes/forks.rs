@@ -0,0 +1,219 @@
+use actix_web::{get, post, HttpRequest, HttpResponse, Responder};
+use actix_web::web::{Data, Json, Path, Query};
+use sqlx::PgPool;
+use destru::decode_sqids;
+use crate::models::creators::CreatorRole;
+use crate::models::ids::{StructureID, STRUCTURE_FLAG};
+use crate::models::pagination::{Pagination, PaginationParams};
+use crate::models::responses::{ForkResponse, StructureError, StructureErrorResponse, StructuresResponse};
+use crate::models::structures::{StructureFork, StructurePreview};
+use crate::routes::auths::authenticate;
+use crate::routes::structures::is_visible;
+use crate::routes::versions::insert_version;
+
+const MAX_NOTE_LENGTH: usize = 1000;
+
+#[get("/structures/{id}/forks")]
+pub async fn get_forks(
+    req: HttpRequest,
+    id: Path<String>,
+    params: Query<PaginationParams>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let page = params.page.unwrap_or(1).max(1);
+    let size = params.size.unwrap_or(16).clamp(1, 64);
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = authenticate(&req, &mut tx).await;
+
+    if !is_visible(id, user, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::NotFound().finish();
+    }
+
+    let total = sqlx::query_scalar!(
+        r"SELECT COUNT(*) FROM structures WHERE parent = $1 AND visibility = 'public' AND published <= NOW()",
+        id
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let structures = sqlx::query_as!(
+        StructurePreview,
+        r"
+        SELECT
+            s.id,
+            s.name,
+            si.url as image,
+            COALESCE(u.slug, u.name) as creator,
+            s.ratings,
+            s.rating_sum::DOUBLE PRECISION / NULLIF(s.ratings, 0) AS rating,
+            s.rating_score
+        FROM structures s
+        LEFT JOIN LATERAL (
+            SELECT COALESCE(v.url, i.url) AS url
+            FROM structure_images i
+            LEFT JOIN structure_image_variants v ON v.image = i.id AND v.size = 'card' AND v.format = 'jpeg'
+            WHERE i.structure = s.id
+            ORDER BY i.id
+            LIMIT 1
+        ) si ON true
+        LEFT JOIN LATERAL (
+            SELECT creator
+            FROM structure_creators
+            WHERE structure = s.id
+            ORDER BY position, id
+            LIMIT 1
+        ) sc ON true
+        LEFT JOIN users u ON u.id = sc.creator
+        WHERE s.parent = $3 AND s.visibility = 'public' AND s.published <= NOW()
+        ORDER BY s.published DESC, s.id DESC
+        LIMIT $1
+        OFFSET $2
+        ",
+        size,
+        (page - 1) * size,
+        id,
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(StructuresResponse {
+        structures,
+        pagination: Pagination {
+            page,
+            size,
+            total: (total + size - 1) / size,
+        },
+    })
+}
+
+/// Copies a structure's metadata, license and latest files into a new draft owned by the caller.
+#[post("/structures/{id}/fork")]
+pub async fn fork_structure(
+    req: HttpRequest,
+    id: Path<String>,
+    fork: Json<StructureFork>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let note = fork.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    if note.is_some_and(|n| n.chars().count() > MAX_NOTE_LENGTH) {
+        return HttpResponse::BadRequest().json(StructureErrorResponse {
+            reason: StructureError::NoteTooLong,
+        });
+    }
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if !is_visible(id, Some(user), &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::NotFound().finish();
+    }
+
+    let parent = sqlx::query!(
+        r#"
+        SELECT s.name, s.summary, s.description, s.latest, v.label AS "label?",
+            s.license, s.license_text, s.requires_attribution, s.allows_commercial_use
+        FROM structures s
+        LEFT JOIN structure_versions v ON v.id = s.latest
+        WHERE s.id = $1
+        "#,
+        id
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap();
+
+    let structure = sqlx::query_scalar!(
+        r"
+        INSERT INTO structures (
+            name, summary, description, parent, parent_note,
+            license, license_text, requires_attribution, allows_commercial_use, created
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, NOW())
+        RETURNING id
+        ",
+        parent.name,
+        parent.summary,
+        parent.description,
+        id,
+        note,
+        parent.license,
+        parent.license_text,
+        parent.requires_attribution,
+        parent.allows_commercial_use
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .expect("failed to insert fork");
+
+    sqlx::query!(
+        r"INSERT INTO structure_creators (structure, creator, role, position) VALUES ($1, $2, $3, $4)",
+        structure,
+        user,
+        CreatorRole::Owner.as_str(),
+        1
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to insert creator");
+
+    let version = insert_version(
+        structure,
+        parent.label.as_deref().unwrap_or("1.0"),
+        "",
+        Some(parent.description),
+        None,
+        &mut tx,
+    )
+        .await
+        .expect("a new structure has no versions");
+
+    sqlx::query!(
+        r"
+        INSERT INTO structure_files (structure, version, name, url, blob, format, metadata, created)
+        SELECT $1, $2, name, url, blob, format, metadata, NOW()
+        FROM structure_files
+        WHERE version = $3
+        ",
+        structure,
+        version,
+        parent.latest
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to copy files");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(ForkResponse {
+        structure: StructureID::from(structure),
+    })
+}
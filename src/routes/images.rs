@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use actix_web::{post, HttpRequest, HttpResponse, Responder};
+use actix_web::web::{Bytes, Data, Path};
+use image::ImageFormat;
+use sqlx::{PgConnection, PgPool};
+use destru::decode_sqids;
+use crate::models::files::{DbImage, DbImageVariant, File, FileVariant};
+use crate::models::ids::{FileID, STRUCTURE_FLAG};
+use crate::models::responses::{ImageError, ImageErrorResponse};
+use crate::routes::auths::authenticate;
+use crate::routes::structures::is_maintainer;
+use crate::storage::local;
+use crate::tasks::images::upload_key;
+
+const MAX_IMAGE_SIZE: usize = 16 * 1024 * 1024;
+
+pub async fn get_images(structure: i64, conn: &mut PgConnection) -> Vec<File> {
+    let images = sqlx::query_as!(
+        DbImage,
+        r"
+        SELECT i.id, i.url, b.hash AS checksum, b.size, i.width, i.height, i.blurhash, i.created
+        FROM structure_images i
+        LEFT JOIN blobs b ON b.hash = i.blob
+        WHERE i.structure = $1
+        ORDER BY i.id
+        ",
+        structure
+    )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap();
+
+    let ids: Vec<i64> = images.iter().map(|i| i.id).collect();
+
+    let mut variants: HashMap<i64, Vec<FileVariant>> = HashMap::new();
+    for variant in sqlx::query_as!(
+        DbImageVariant,
+        r"SELECT image, size, format, url, width, height FROM structure_image_variants WHERE image = ANY($1) ORDER BY width",
+        &ids
+    )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap()
+    {
+        variants.entry(variant.image).or_default().push(FileVariant {
+            size: variant.size,
+            format: variant.format,
+            url: variant.url,
+            width: variant.width,
+            height: variant.height,
+        });
+    }
+
+    images
+        .into_iter()
+        .map(|image| File {
+            id: FileID::from(image.id),
+            variants: variants.remove(&image.id).unwrap_or_default(),
+            name: None,
+            url: image.url,
+            checksum: image.checksum,
+            size: image.size,
+            width: image.width,
+            height: image.height,
+            blurhash: image.blurhash,
+            format: None,
+            metadata: None,
+            created: image.created,
+        })
+        .collect()
+}
+
+#[post("/structures/{id}/images")]
+pub async fn upload_image(
+    req: HttpRequest,
+    id: Path<String>,
+    body: Bytes,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    if body.len() > MAX_IMAGE_SIZE {
+        return HttpResponse::PayloadTooLarge().json(ImageErrorResponse {
+            reason: ImageError::TooLarge,
+        });
+    }
+
+    match image::guess_format(&body) {
+        Ok(ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP | ImageFormat::Gif) => {}
+        _ => return HttpResponse::BadRequest().json(ImageErrorResponse {
+            reason: ImageError::UnsupportedFormat,
+        }),
+    }
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if !is_maintainer(id, user, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let upload = sqlx::query_scalar!(
+        r"INSERT INTO structure_image_uploads (structure) VALUES ($1) RETURNING id",
+        id
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .expect("failed to insert image upload");
+
+    // Processing happens in the background worker, which picks the upload up once committed.
+    if let Err(e) = local::write(&upload_key(upload), body.to_vec()).await {
+        log::error!("failed to store image upload: {:?}", e);
+
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Accepted().finish()
+}
@@ -1,11 +1,39 @@
 mod structures;
 mod users;
 mod auths;
+mod comments;
+pub mod versions;
+mod creators;
+mod images;
+pub mod files;
+mod archives;
+mod forks;
+mod collections;
+mod ratings;
+mod limits;
+mod follows;
+mod blocks;
+mod policy;
+mod notifications;
+mod storage;
 
 use actix_web::web;
 use crate::routes::auths::{login, logout, register};
-use crate::routes::structures::{get_structure, get_structures};
-use crate::routes::users::{get_user, get_user_by, get_users};
+use crate::routes::creators::{accept_invitation, decline_invitation, get_invitations, invite_creator, leave_structure, order_creators, remove_creator, transfer_ownership, update_creator};
+use crate::routes::images::upload_image;
+use crate::routes::files::upload_file;
+use crate::routes::archives::{export_structure, import_structure};
+use crate::routes::forks::{fork_structure, get_forks};
+use crate::routes::collections::{add_collection_item, create_collection, delete_collection, get_collection, get_user_collections, order_collection_items, remove_collection_item, update_collection};
+use crate::routes::ratings::{delete_rating, get_rating, rate_structure};
+use crate::routes::follows::{follow_user, get_feed, get_followers, get_following, unfollow_user};
+use crate::routes::blocks::{block_user, get_blocks, get_mutes, mute_user, unblock_user, unmute_user};
+use crate::routes::notifications::{get_notification_preferences, get_notifications, get_unread_count, mark_all_notifications_read, mark_notification_read, stream_notifications, update_notification_preference};
+use crate::routes::comments::{create_comment, delete_comment, edit_comment, get_comments};
+use crate::routes::storage::get_blob;
+use crate::routes::structures::{download_structure_file, get_structure, get_structures, get_trending_structures, get_user_structures, publish_structure, set_structure_license, set_structure_visibility};
+use crate::routes::versions::{create_version, get_version, get_versions, mark_latest_version};
+use crate::routes::users::{delete_avatar, get_my_privacy, get_user, get_user_by, get_user_by_handle, get_users, update_me, update_my_privacy, upload_avatar};
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
@@ -13,10 +41,75 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .service(register)
             .service(login)
             .service(logout)
+            .service(get_trending_structures)
             .service(get_structure)
             .service(get_structures)
+            .service(download_structure_file)
+            .service(publish_structure)
+            .service(set_structure_visibility)
+            .service(set_structure_license)
+            .service(get_comments)
+            .service(create_comment)
+            .service(edit_comment)
+            .service(delete_comment)
+            .service(get_versions)
+            .service(get_version)
+            .service(create_version)
+            .service(mark_latest_version)
+            .service(invite_creator)
+            .service(get_invitations)
+            .service(accept_invitation)
+            .service(decline_invitation)
+            .service(update_creator)
+            .service(remove_creator)
+            .service(order_creators)
+            .service(transfer_ownership)
+            .service(leave_structure)
+            .service(upload_image)
+            .service(upload_file)
+            .service(export_structure)
+            .service(import_structure)
+            .service(get_blob)
+            .service(get_forks)
+            .service(fork_structure)
+            .service(get_collection)
+            .service(get_user_collections)
+            .service(get_user_structures)
+            .service(follow_user)
+            .service(unfollow_user)
+            .service(get_blocks)
+            .service(get_mutes)
+            .service(block_user)
+            .service(unblock_user)
+            .service(mute_user)
+            .service(unmute_user)
+            .service(get_followers)
+            .service(get_following)
+            .service(get_feed)
+            .service(get_unread_count)
+            .service(stream_notifications)
+            .service(mark_all_notifications_read)
+            .service(get_notification_preferences)
+            .service(update_notification_preference)
+            .service(get_notifications)
+            .service(mark_notification_read)
+            .service(create_collection)
+            .service(update_collection)
+            .service(delete_collection)
+            .service(add_collection_item)
+            .service(remove_collection_item)
+            .service(order_collection_items)
+            .service(get_rating)
+            .service(rate_structure)
+            .service(delete_rating)
+            .service(get_user_by_handle)
             .service(get_user)
             .service(get_user_by)
+            .service(update_me)
+            .service(upload_avatar)
+            .service(delete_avatar)
+            .service(get_my_privacy)
+            .service(update_my_privacy)
             .service(get_users)
     );
 }
\ No newline at end of file
@@ -0,0 +1,408 @@
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::time::Duration;
+use actix_web::{get, post, put, HttpRequest, HttpResponse, Responder};
+use actix_web::http::header;
+use actix_web::web::{Bytes, Data, Json, Path, Query};
+use sqlx::{PgConnection, PgPool};
+use tokio::sync::mpsc::UnboundedReceiver;
+use destru::{decode_sqids, encode_sqids};
+use crate::models::ids::NOTIFICATION_FLAG;
+use crate::models::notifications::{DbNotification, Notification, NotificationChannel, NotificationCursor, NotificationKind, NotificationPreference, NotificationPreferenceUpdate};
+use crate::models::pagination::{CursorPagination, CursorParams};
+use crate::models::responses::{NotificationError, NotificationErrorResponse, NotificationPreferencesResponse, NotificationsResponse, UnreadResponse};
+use crate::notifications::is_enabled;
+use crate::notifications::live::Hub;
+use crate::routes::auths::authenticate;
+
+/// How long a stream may stay silent before a heartbeat keeps proxies from closing it.
+const HEARTBEAT: Duration = Duration::from_secs(20);
+
+/// How many missed notifications a resumed stream replays; older ones are left to `GET /notifications`.
+const REPLAY_LIMIT: i64 = 64;
+
+async fn count_unread(user: i64, conn: &mut PgConnection) -> i64 {
+    sqlx::query_scalar!(
+        r#"SELECT COUNT(*) FROM notifications WHERE "user" = $1 AND read IS NULL"#,
+        user
+    )
+        .fetch_one(conn)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+/// The caller's notifications, unread ones first and newest first within each group.
+#[get("/notifications")]
+pub async fn get_notifications(
+    req: HttpRequest,
+    params: Query<CursorParams<NotificationCursor>>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let size = params.size.unwrap_or(16).clamp(1, 64);
+    let cursor = params.cursor;
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    // Rows sort by (read, -id), so the cursor continues after its own position in that order.
+    let mut notifications: Vec<Notification> = sqlx::query_as!(
+        DbNotification,
+        r#"
+        SELECT
+            n.id,
+            n.kind,
+            u.name AS "actor_name?",
+            u.avatar AS actor_avatar,
+            u.slug AS actor_slug,
+            n.structure,
+            s.name AS "structure_name?",
+            n.comment,
+            n.read IS NOT NULL AS "read!",
+            n.created
+        FROM notifications n
+        LEFT JOIN users u ON u.id = n.actor
+        LEFT JOIN structures s ON s.id = n.structure
+        WHERE n."user" = $1
+            AND (
+                $2::BIGINT IS NULL
+                OR (n.read IS NOT NULL, -n.id) > ($3::BOOLEAN, -$2)
+            )
+        ORDER BY n.read IS NOT NULL, n.id DESC
+        LIMIT $4
+        "#,
+        user,
+        cursor.map(|c| c.notification.0),
+        cursor.is_some_and(|c| c.read),
+        size + 1,
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(Notification::from)
+        .collect();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    let next = if notifications.len() as i64 > size {
+        notifications.truncate(size as usize);
+        notifications.last().map(|n| NotificationCursor {
+            read: n.read,
+            notification: n.id,
+        })
+    } else {
+        None
+    };
+
+    HttpResponse::Ok().json(NotificationsResponse {
+        notifications,
+        pagination: CursorPagination { next, size },
+    })
+}
+
+#[get("/notifications/unread")]
+pub async fn get_unread_count(req: HttpRequest, postgre: Data<PgPool>) -> impl Responder {
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let unread = count_unread(user, &mut tx).await;
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(UnreadResponse { unread })
+}
+
+/// Pushes the caller's new notifications and unread count as server-sent events.
+///
+/// A reconnecting client's `Last-Event-ID` replays whatever arrived after that notification.
+/// Comment lines are sent while idle so intermediaries keep the connection open.
+#[get("/notifications/stream")]
+pub async fn stream_notifications(req: HttpRequest, postgre: Data<PgPool>, hub: Data<Hub>) -> impl Responder {
+    let resume = req.headers()
+        .get("Last-Event-ID")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| decode_sqids(NOTIFICATION_FLAG, value).ok());
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    // Subscribing before reading the latest id means nothing stored in between is missed.
+    let signals = hub.subscribe(user);
+
+    let last = match resume {
+        Some(last) => last,
+        None => sqlx::query_scalar!(
+            r#"SELECT COALESCE(MAX(id), 0) AS "last!" FROM notifications WHERE "user" = $1"#,
+            user
+        )
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap(),
+    };
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    let mut stream = NotificationStream {
+        user,
+        last,
+        signals,
+        postgre,
+        pending: VecDeque::from([Bytes::from_static(b"retry: 5000\n\n")]),
+    };
+
+    if stream.refresh().await.is_none() {
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    let body = futures_util::stream::unfold(stream, |mut stream| async move {
+        let bytes = stream.next().await?;
+        Some((Ok::<_, Infallible>(bytes), stream))
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(body)
+}
+
+struct NotificationStream {
+    user: i64,
+    /// The newest notification already sent.
+    last: i64,
+    signals: UnboundedReceiver<()>,
+    postgre: Data<PgPool>,
+    pending: VecDeque<Bytes>,
+}
+
+impl NotificationStream {
+    /// The next chunk of the response, or `None` once the stream should end.
+    async fn next(&mut self) -> Option<Bytes> {
+        while self.pending.is_empty() {
+            match actix_rt::time::timeout(HEARTBEAT, self.signals.recv()).await {
+                Err(_) => return Some(Bytes::from_static(b": heartbeat\n\n")),
+                Ok(None) => return None,
+                Ok(Some(())) => {
+                    // A burst of changes only needs one round trip.
+                    while self.signals.try_recv().is_ok() {}
+
+                    self.refresh().await?;
+                }
+            }
+        }
+
+        self.pending.pop_front()
+    }
+
+    /// Queues notifications newer than the last one sent, followed by the unread count.
+    async fn refresh(&mut self) -> Option<()> {
+        let mut conn = self.postgre.acquire().await.ok()?;
+
+        let mut notifications = sqlx::query_as!(
+            DbNotification,
+            r#"
+            SELECT
+                n.id,
+                n.kind,
+                u.name AS "actor_name?",
+                u.avatar AS actor_avatar,
+                u.slug AS actor_slug,
+                n.structure,
+                s.name AS "structure_name?",
+                n.comment,
+                n.read IS NOT NULL AS "read!",
+                n.created
+            FROM notifications n
+            LEFT JOIN users u ON u.id = n.actor
+            LEFT JOIN structures s ON s.id = n.structure
+            WHERE n."user" = $1 AND n.id > $2
+            ORDER BY n.id DESC
+            LIMIT $3
+            "#,
+            self.user,
+            self.last,
+            REPLAY_LIMIT
+        )
+            .fetch_all(&mut *conn)
+            .await
+            .ok()?;
+        notifications.reverse();
+
+        for notification in notifications {
+            let id = encode_sqids(NOTIFICATION_FLAG, notification.id.0).ok()?;
+            self.last = notification.id.0;
+
+            let data = serde_json::to_string(&Notification::from(notification)).ok()?;
+            self.pending.push_back(Bytes::from(format!("id: {}\nevent: notification\ndata: {}\n\n", id, data)));
+        }
+
+        let unread = count_unread(self.user, &mut conn).await;
+        let data = serde_json::to_string(&UnreadResponse { unread }).ok()?;
+        self.pending.push_back(Bytes::from(format!("event: unread\ndata: {}\n\n", data)));
+
+        Some(())
+    }
+}
+
+#[post("/notifications/{id}/read")]
+pub async fn mark_notification_read(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let id = match decode_sqids(NOTIFICATION_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let updated = sqlx::query!(
+        r#"UPDATE notifications SET read = COALESCE(read, NOW()) WHERE id = $1 AND "user" = $2"#,
+        id,
+        user
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to mark notification read")
+        .rows_affected();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    if updated == 0 {
+        HttpResponse::NotFound().finish()
+    } else {
+        HttpResponse::Ok().finish()
+    }
+}
+
+#[post("/notifications/read")]
+pub async fn mark_all_notifications_read(req: HttpRequest, postgre: Data<PgPool>) -> impl Responder {
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    sqlx::query!(
+        r#"UPDATE notifications SET read = NOW() WHERE "user" = $1 AND read IS NULL"#,
+        user
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to mark notifications read");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
+
+/// Every kind and channel, with the caller's choice or the enabled default.
+#[get("/notifications/preferences")]
+pub async fn get_notification_preferences(req: HttpRequest, postgre: Data<PgPool>) -> impl Responder {
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let mut preferences = Vec::with_capacity(NotificationKind::ALL.len() * NotificationChannel::ALL.len());
+    for kind in NotificationKind::ALL {
+        for channel in NotificationChannel::ALL {
+            preferences.push(NotificationPreference {
+                kind,
+                channel,
+                enabled: is_enabled(user, kind, channel, &mut tx).await,
+            });
+        }
+    }
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(NotificationPreferencesResponse { preferences })
+}
+
+#[put("/notifications/preferences")]
+pub async fn update_notification_preference(
+    req: HttpRequest,
+    update: Json<NotificationPreferenceUpdate>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let (kind, enabled) = match (update.kind, update.enabled) {
+        (Some(kind), Some(enabled)) => (kind, enabled),
+        _ => return HttpResponse::BadRequest().json(NotificationErrorResponse {
+            reason: NotificationError::InvalidPreference,
+        }),
+    };
+    let channel = update.channel.unwrap_or(NotificationChannel::InApp);
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    sqlx::query!(
+        r#"
+        INSERT INTO notification_preferences ("user", kind, channel, enabled)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT ("user", kind, channel) DO UPDATE SET enabled = EXCLUDED.enabled
+        "#,
+        user,
+        kind.as_str(),
+        channel.as_str(),
+        enabled
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to update notification preference");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
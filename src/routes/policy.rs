@@ -0,0 +1,102 @@
+use sqlx::PgConnection;
+use crate::routes::auths::is_staff;
+
+/// Whose account or content an interaction reaches.
+pub enum Target {
+    /// The user themselves, as when following them or inviting them as a co-creator.
+    User(i64),
+    /// Every creator of a structure, as when commenting on or rating it.
+    Structure(i64),
+    /// The author of a comment, as when replying to it.
+    Comment(i64),
+}
+
+/// Whether `actor` may interact with `target`, which is forbidden once anyone behind it has
+/// blocked them.
+///
+/// Mutes only hide content from the muter and are applied in listings through the `is_muted`
+/// database function instead.
+pub async fn may_interact(actor: i64, target: Target, conn: &mut PgConnection) -> bool {
+    let blocked = match target {
+        Target::User(user) => sqlx::query_scalar!(
+            r"SELECT EXISTS(SELECT 1 FROM user_blocks WHERE blocker = $1 AND blocked = $2)",
+            user,
+            actor
+        )
+            .fetch_one(conn)
+            .await,
+        Target::Structure(structure) => sqlx::query_scalar!(
+            r"
+            SELECT EXISTS(
+                SELECT 1
+                FROM structure_creators c
+                JOIN user_blocks b ON b.blocker = c.creator
+                WHERE c.structure = $1 AND b.blocked = $2
+            )
+            ",
+            structure,
+            actor
+        )
+            .fetch_one(conn)
+            .await,
+        Target::Comment(comment) => sqlx::query_scalar!(
+            r"
+            SELECT EXISTS(
+                SELECT 1
+                FROM structure_comments c
+                JOIN user_blocks b ON b.blocker = c.author
+                WHERE c.id = $1 AND b.blocked = $2
+            )
+            ",
+            comment,
+            actor
+        )
+            .fetch_one(conn)
+            .await,
+    };
+
+    !blocked.unwrap().unwrap()
+}
+
+/// A part of a user's profile that their privacy settings can hide.
+pub enum ProfileSection {
+    /// Everything beyond the name.
+    Profile,
+    Structures,
+    /// Who the user follows.
+    Activity,
+    Collections,
+    Followers,
+}
+
+/// Whether `viewer` may see a section of `user`'s profile.
+///
+/// Users always see their own profile, and staff see every profile for moderation.
+pub async fn may_view(viewer: Option<i64>, user: i64, section: ProfileSection, conn: &mut PgConnection) -> bool {
+    if viewer == Some(user) {
+        return true;
+    }
+
+    let settings = sqlx::query!(
+        r"SELECT private, hide_activity, hide_collections, hide_followers FROM users WHERE id = $1",
+        user
+    )
+        .fetch_optional(&mut *conn)
+        .await
+        .unwrap();
+
+    let hidden = match settings {
+        Some(settings) => settings.private || match section {
+            ProfileSection::Profile | ProfileSection::Structures => false,
+            ProfileSection::Activity => settings.hide_activity,
+            ProfileSection::Collections => settings.hide_collections,
+            ProfileSection::Followers => settings.hide_followers,
+        },
+        None => false,
+    };
+
+    match viewer {
+        Some(viewer) if hidden => is_staff(viewer, conn).await,
+        _ => !hidden,
+    }
+}
@@ -0,0 +1,167 @@
+use actix_web::{delete, get, put, HttpRequest, HttpResponse, Responder};
+use actix_web::web::{Data, Json, Path};
+use sqlx::PgPool;
+use destru::decode_sqids;
+use crate::models::ids::STRUCTURE_FLAG;
+use crate::models::notifications::NotificationKind;
+use crate::models::responses::{BlockError, BlockErrorResponse, RatingError, RatingErrorResponse, RatingResponse};
+use crate::models::structures::StructureRating;
+use crate::notifications;
+use crate::routes::auths::authenticate;
+use crate::routes::policy::{may_interact, Target};
+use crate::routes::structures::{is_creator, is_visible};
+
+/// The caller's own rating of a structure, if any.
+#[get("/structures/{id}/rating")]
+pub async fn get_rating(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if !is_visible(id, Some(user), &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::NotFound().finish();
+    }
+
+    let rating = sqlx::query_scalar!(
+        r#"SELECT rating FROM structure_ratings WHERE structure = $1 AND "user" = $2"#,
+        id,
+        user
+    )
+        .fetch_optional(&mut *tx)
+        .await
+        .unwrap();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(RatingResponse { rating })
+}
+
+#[put("/structures/{id}/rating")]
+pub async fn rate_structure(
+    req: HttpRequest,
+    id: Path<String>,
+    rating: Json<StructureRating>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let rating = match rating.rating {
+        Some(rating) if (1..=5).contains(&rating) => rating,
+        _ => return HttpResponse::BadRequest().json(RatingErrorResponse {
+            reason: RatingError::InvalidRating,
+        }),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if !is_visible(id, Some(user), &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::NotFound().finish();
+    }
+
+    if is_creator(id, user, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::BadRequest().json(RatingErrorResponse {
+            reason: RatingError::OwnStructure,
+        });
+    }
+
+    if !may_interact(user, Target::Structure(id), &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().json(BlockErrorResponse {
+            reason: BlockError::Blocked,
+        });
+    }
+
+    let inserted = sqlx::query_scalar!(
+        r#"
+        INSERT INTO structure_ratings (structure, "user", rating)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (structure, "user") DO UPDATE SET rating = EXCLUDED.rating, updated = NOW()
+        RETURNING (xmax = 0) AS "inserted!"
+        "#,
+        id,
+        user,
+        rating
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .expect("failed to insert rating");
+
+    // Changing an existing rating doesn't notify again.
+    if inserted {
+        notifications::dispatch_to_creators(id, NotificationKind::Rating, Some(user), None, &mut tx).await;
+    }
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(RatingResponse {
+        rating: Some(rating),
+    })
+}
+
+#[delete("/structures/{id}/rating")]
+pub async fn delete_rating(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let removed = sqlx::query!(
+        r#"DELETE FROM structure_ratings WHERE structure = $1 AND "user" = $2"#,
+        id,
+        user
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to delete rating")
+        .rows_affected();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    if removed == 0 {
+        HttpResponse::NotFound().finish()
+    } else {
+        HttpResponse::Ok().finish()
+    }
+}
@@ -0,0 +1,86 @@
+use std::io;
+use actix_web::{get, HttpResponse, Responder};
+use actix_web::web::{Bytes, Path};
+use lazy_static::lazy_static;
+use regex::Regex;
+use tokio::io::AsyncReadExt;
+use crate::storage::{blobs, local};
+
+lazy_static! {
+    static ref HASH_REGEX: Regex = Regex::new(r"^[0-9a-f]{64}$").unwrap();
+}
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Serves stored blobs when `STORAGE_URL` points back at this server.
+///
+/// Blobs are addressed by their hash and never change, so they may be cached forever.
+#[get("/storage/blobs/{prefix}/{hash}")]
+pub async fn get_blob(path: Path<(String, String)>) -> impl Responder {
+    let (prefix, hash) = path.into_inner();
+
+    if !HASH_REGEX.is_match(&hash) || prefix != hash[..2] {
+        return HttpResponse::NotFound().finish();
+    }
+
+    let mut file = match local::open(&blobs::key(&hash)).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return HttpResponse::NotFound().finish(),
+        Err(e) => {
+            log::error!("failed to open blob {}: {:?}", hash, e);
+
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let size = match file.metadata().await {
+        Ok(metadata) => metadata.len(),
+        Err(e) => {
+            log::error!("failed to stat blob {}: {:?}", hash, e);
+
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    // The first chunk is read up front to sniff the content type, then sent as the start of the body.
+    let head = match read_chunk(&mut file).await {
+        Ok(head) => head,
+        Err(e) => {
+            log::error!("failed to read blob {}: {:?}", hash, e);
+
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let content_type = image::guess_format(&head)
+        .map(|format| format.to_mime_type())
+        .unwrap_or("application/octet-stream");
+
+    let body = futures_util::stream::unfold((Some(head), Some(file)), |(head, file)| async move {
+        if let Some(head) = head.filter(|head| !head.is_empty()) {
+            return Some((Ok(head), (None, file)));
+        }
+
+        let mut file = file?;
+        match read_chunk(&mut file).await {
+            Ok(chunk) if chunk.is_empty() => None,
+            Ok(chunk) => Some((Ok(chunk), (None, Some(file)))),
+            Err(e) => Some((Err::<Bytes, io::Error>(e), (None, None))),
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(("Cache-Control", "public, max-age=31536000, immutable"))
+        .insert_header(("ETag", format!("\"{}\"", hash)))
+        .insert_header(("X-Content-Type-Options", "nosniff"))
+        .no_chunking(size)
+        .streaming(body)
+}
+
+async fn read_chunk(file: &mut tokio::fs::File) -> io::Result<Bytes> {
+    let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+    (&mut *file).take(CHUNK_SIZE as u64).read_to_end(&mut chunk).await?;
+
+    Ok(Bytes::from(chunk))
+}
@@ -1,68 +1,265 @@
-use crate::models::files::File;
+use crate::models::files::{DbFile, File};
 use crate::models::pagination::{Pagination, PaginationParams};
-use crate::models::responses::{StructureResponse, StructuresResponse};
-use crate::models::structures::{DbStructure, Structure, StructurePreview};
-use crate::models::users::UserPreview;
-use actix_web::web::{Data, Path, Query};
-use actix_web::{get, HttpResponse, Responder};
-use sqlx::PgPool;
+use crate::models::responses::{StructureError, StructureErrorResponse, StructureResponse, StructuresResponse};
+use crate::models::structures::{DbStructure, License, LicenseUpdate, Structure, StructureParent, StructurePreview, StructurePublish, StructureVisibility, StructuresParams, TrendingParams, Visibility, is_valid_license};
+use crate::models::creators::{Creator, CreatorRole};
+use crate::models::ids::UserID;
+use crate::models::versions::VersionParams;
+use actix_web::http::header;
+use actix_web::web::{Data, Json, Path, Query};
+use actix_web::{get, post, put, HttpRequest, HttpResponse, Responder};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chrono::Utc;
+use sqlx::{PgConnection, PgPool};
 use destru::decode_sqids;
-use crate::models::ids::STRUCTURE_FLAG;
+use crate::models::ids::{StructureID, FILE_FLAG, STRUCTURE_FLAG, USER_FLAG};
+use crate::routes::auths::{authenticate, client_ip};
+use crate::routes::images::get_images;
+use crate::routes::policy::{may_view, ProfileSection};
+
+pub async fn is_creator(structure: i64, user: i64, conn: &mut PgConnection) -> bool {
+    sqlx::query_scalar!(
+        r"SELECT EXISTS(SELECT 1 FROM structure_creators WHERE structure = $1 AND creator = $2)",
+        structure,
+        user
+    )
+        .fetch_one(conn)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+pub async fn creator_role(structure: i64, user: i64, conn: &mut PgConnection) -> Option<CreatorRole> {
+    sqlx::query_scalar!(
+        r"SELECT role FROM structure_creators WHERE structure = $1 AND creator = $2",
+        structure,
+        user
+    )
+        .fetch_optional(conn)
+        .await
+        .unwrap()
+        .map(|role| CreatorRole::parse(&role))
+}
+
+/// Whether `user` may change what a structure publishes: its files, images, versions,
+/// visibility and license. Contributors can't.
+pub async fn is_maintainer(structure: i64, user: i64, conn: &mut PgConnection) -> bool {
+    matches!(
+        creator_role(structure, user, conn).await,
+        Some(CreatorRole::Owner | CreatorRole::Maintainer)
+    )
+}
+
+/// Whether `user` may see the structure, hiding drafts and private ones from everyone but creators.
+pub async fn is_visible(structure: i64, user: Option<i64>, conn: &mut PgConnection) -> bool {
+    let visibility = sqlx::query_scalar!(
+        r"SELECT visibility FROM structures WHERE id = $1",
+        structure
+    )
+        .fetch_optional(&mut *conn)
+        .await
+        .unwrap();
+
+    match visibility {
+        Some(visibility) if Visibility::parse(&visibility).is_restricted() => match user {
+            Some(user) => is_creator(structure, user, conn).await,
+            None => false,
+        },
+        Some(_) => true,
+        None => false,
+    }
+}
 
 #[get("/structures/{id}")]
-pub async fn get_structure(id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+pub async fn get_structure(
+    req: HttpRequest,
+    id: Path<String>,
+    params: Query<VersionParams>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
     match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
         Ok(id) => {
             let mut tx = postgre.begin().await.unwrap();
 
+            let user = authenticate(&req, &mut tx).await;
+
+            if !is_visible(id, user, &mut tx).await {
+                tx.commit().await.expect("failed to commit transaction");
+
+                return HttpResponse::NotFound().finish();
+            }
+
             let db_structure = sqlx::query_as!(
                 DbStructure,
-                r"SELECT id, name, summary, description, created FROM structures WHERE id = $1",
+                r"
+                SELECT id, name, summary, description, description_html, visibility, published, parent, parent_note,
+                    license, license_text, requires_attribution, allows_commercial_use, created
+                FROM structures
+                WHERE id = $1
+                ",
                 id,
             )
             .fetch_one(&mut *tx)
             .await
             .unwrap();
 
-            let files = sqlx::query_as!(
-                File,
-                r"SELECT url, created FROM structure_files WHERE structure = $1",
+            let version = sqlx::query!(
+                r"
+                SELECT id, label, description, description_html
+                FROM structure_versions
+                WHERE structure = $1
+                    AND CASE
+                        WHEN $2::TEXT IS NULL THEN id = (SELECT latest FROM structures WHERE id = $1)
+                        ELSE label = $2
+                    END
+                ",
                 id,
+                params.version,
             )
-            .fetch_all(&mut *tx)
-            .await
-            .unwrap();
+                .fetch_optional(&mut *tx)
+                .await
+                .unwrap();
+
+            if version.is_none() && params.version.is_some() {
+                tx.commit().await.expect("failed to commit transaction");
 
-            let images = sqlx::query_as!(
-                File,
-                r"SELECT url, created FROM structure_images WHERE structure = $1",
+                return HttpResponse::NotFound().finish();
+            }
+
+            let viewer = match user {
+                Some(user) => Some(format!("user:{}", user)),
+                None => client_ip(&req).map(|ip| format!("ip:{}", ip)),
+            };
+            if let Some(viewer) = viewer {
+                record_view(id, &viewer, &mut tx).await;
+            }
+
+            let files: Vec<File> = sqlx::query_as!(
+                DbFile,
+                r"
+                SELECT f.id, f.name, f.url, b.hash AS checksum, b.size, f.format, f.metadata, f.created
+                FROM structure_files f
+                LEFT JOIN blobs b ON b.hash = f.blob
+                WHERE f.structure = $1 AND ($2::BIGINT IS NULL OR f.version = $2)
+                ",
                 id,
+                version.as_ref().map(|v| v.id),
             )
             .fetch_all(&mut *tx)
             .await
-            .unwrap();
+            .unwrap()
+            .into_iter()
+            .map(File::from)
+            .collect();
+
+            let images = get_images(id, &mut tx).await;
 
-            let creators = sqlx::query_as!(
-                UserPreview,
-                r"SELECT u.name, u.avatar, u.slug FROM structure_creators c JOIN users u ON c.id = u.id WHERE c.structure = $1",
+            let creators = sqlx::query!(
+                r"
+                SELECT u.id, u.name, u.avatar, u.slug, c.role
+                FROM structure_creators c
+                JOIN users u ON c.creator = u.id
+                WHERE c.structure = $1
+                ORDER BY c.position, c.id
+                ",
                 id,
             )
                 .fetch_all(&mut *tx)
                 .await
+                .unwrap()
+                .into_iter()
+                .map(|record| Creator {
+                    id: UserID::from(record.id),
+                    name: record.name,
+                    avatar: record.avatar,
+                    slug: record.slug,
+                    role: CreatorRole::parse(&record.role),
+                })
+                .collect();
+
+            let downloads = sqlx::query_scalar!(
+                r"SELECT SUM(downloads)::BIGINT FROM structure_downloads_daily WHERE structure = $1",
+                id,
+            )
+                .fetch_one(&mut *tx)
+                .await
+                .unwrap()
+                .unwrap_or(0);
+
+            let ratings = sqlx::query!(
+                r"
+                SELECT ratings, rating_sum::DOUBLE PRECISION / NULLIF(ratings, 0) AS rating, rating_score
+                FROM structures
+                WHERE id = $1
+                ",
+                id,
+            )
+                .fetch_one(&mut *tx)
+                .await
+                .unwrap();
+
+            // A parent that the viewer can't see is left out rather than leaking its name.
+            let parent = match db_structure.parent {
+                Some(parent) if is_visible(parent, user, &mut tx).await => {
+                    let name = sqlx::query_scalar!(
+                        r"SELECT name FROM structures WHERE id = $1",
+                        parent,
+                    )
+                        .fetch_one(&mut *tx)
+                        .await
+                        .unwrap();
+
+                    Some(StructureParent {
+                        id: StructureID::from(parent),
+                        name,
+                        note: db_structure.parent_note,
+                    })
+                }
+                _ => None,
+            };
+
+            let forks = sqlx::query_scalar!(
+                r"SELECT COUNT(*) FROM structures WHERE parent = $1 AND visibility = 'public' AND published <= NOW()",
+                id,
+            )
+                .fetch_one(&mut *tx)
+                .await
+                .unwrap()
                 .unwrap();
 
             tx.commit().await.expect("failed to commit transaction");
 
+            let (version, description, description_html) = match version {
+                Some(version) => (Some(version.label), version.description, version.description_html),
+                None => (None, db_structure.description, db_structure.description_html),
+            };
+
             HttpResponse::Ok().json(StructureResponse {
                 structure: Structure {
                     id: db_structure.id,
                     name: db_structure.name,
                     summary: db_structure.summary,
-                    description: db_structure.description,
+                    description,
+                    description_html: description_html.unwrap_or_default(),
+                    version,
                     created: db_structure.created,
                     files,
                     images,
                     creators,
+                    downloads,
+                    ratings: ratings.ratings,
+                    rating: ratings.rating,
+                    rating_score: ratings.rating_score,
+                    visibility: Visibility::parse(&db_structure.visibility),
+                    published: db_structure.published,
+                    parent,
+                    forks,
+                    license: db_structure.license.map(|license| License {
+                        id: license,
+                        text: db_structure.license_text,
+                        requires_attribution: db_structure.requires_attribution,
+                        allows_commercial_use: db_structure.allows_commercial_use,
+                    }),
                 }
             })
         }
@@ -70,15 +267,55 @@ pub async fn get_structure(id: Path<String>, postgre: Data<PgPool>) -> impl Resp
     }
 }
 
+/// Counts a view towards trending, at most once a day per viewer.
+async fn record_view(structure: i64, viewer: &str, conn: &mut PgConnection) {
+    // Rows older than a day are purged by `tasks::views`, but one that lingers is claimed afresh.
+    let counted = sqlx::query_scalar!(
+        r"
+        INSERT INTO structure_views (structure, viewer)
+        VALUES ($1, $2)
+        ON CONFLICT (structure, viewer)
+        DO UPDATE SET created = NOW() WHERE structure_views.created < NOW() - INTERVAL '1 day'
+        RETURNING structure
+        ",
+        structure,
+        viewer,
+    )
+        .fetch_optional(&mut *conn)
+        .await
+        .expect("failed to insert view");
+
+    if counted.is_some() {
+        sqlx::query!(
+            r"
+            INSERT INTO structure_views_daily (structure, day, views)
+            VALUES ($1, CURRENT_DATE, 1)
+            ON CONFLICT (structure, day)
+            DO UPDATE SET views = structure_views_daily.views + 1
+            ",
+            structure,
+        )
+            .execute(&mut *conn)
+            .await
+            .expect("failed to count view");
+    }
+}
+
 #[get("/structures")]
-pub async fn get_structures(params: Query<PaginationParams>, postgre: Data<PgPool>) -> impl Responder {
+pub async fn get_structures(
+    params: Query<PaginationParams>,
+    filter: Query<StructuresParams>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
     let page = params.page.unwrap_or(1).max(1);
-    let size = params.size.unwrap_or(16).max(1).min(64);
+    let size = params.size.unwrap_or(16).clamp(1, 64);
+    let sort = filter.sort.unwrap_or_default();
 
     let mut tx = postgre.begin().await.unwrap();
 
     let total = sqlx::query_scalar!(
-        r"SELECT COUNT(*) FROM structures"
+        r"SELECT COUNT(*) FROM structures WHERE visibility = 'public' AND published <= NOW() AND ($1::TEXT IS NULL OR license = $1)",
+        filter.license,
     )
         .fetch_one(&mut *tx)
         .await
@@ -92,28 +329,158 @@ pub async fn get_structures(params: Query<PaginationParams>, postgre: Data<PgPoo
             s.id,
             s.name,
             si.url as image,
-            COALESCE(u.slug, u.name) as creator
+            COALESCE(u.slug, u.name) as creator,
+            s.ratings,
+            s.rating_sum::DOUBLE PRECISION / NULLIF(s.ratings, 0) AS rating,
+            s.rating_score
         FROM structures s
         LEFT JOIN LATERAL (
-            SELECT url
-            FROM structure_images
+            SELECT COALESCE(v.url, i.url) AS url
+            FROM structure_images i
+            LEFT JOIN structure_image_variants v ON v.image = i.id AND v.size = 'card' AND v.format = 'jpeg'
+            WHERE i.structure = s.id
+            ORDER BY i.id
+            LIMIT 1
+        ) si ON true
+        LEFT JOIN LATERAL (
+            SELECT creator
+            FROM structure_creators
+            WHERE structure = s.id
+            ORDER BY position, id
+            LIMIT 1
+        ) sc ON true
+        LEFT JOIN users u ON u.id = sc.creator
+        LEFT JOIN LATERAL (
+            SELECT SUM(downloads) AS downloads
+            FROM structure_downloads_daily
             WHERE structure = s.id
-            ORDER BY id
+        ) sd ON true
+        WHERE s.visibility = 'public' AND s.published <= NOW() AND ($4::TEXT IS NULL OR s.license = $4)
+        ORDER BY
+            CASE WHEN $3 = 'downloads' THEN sd.downloads END DESC NULLS LAST,
+            CASE WHEN $3 = 'top_rated' THEN s.rating_score END DESC NULLS LAST,
+            s.id
+        LIMIT $1
+        OFFSET $2
+        ",
+        size,
+        (page - 1) * size,
+        sort.as_str(),
+        filter.license,
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(StructuresResponse {
+        structures,
+        pagination: Pagination {
+            page,
+            size,
+            total: (total + size - 1) / size,
+        },
+    })
+}
+
+/// Structures the user is a creator of, including unpublished ones when they look at their own.
+#[get("/users/{id}/structures")]
+pub async fn get_user_structures(
+    req: HttpRequest,
+    id: Path<String>,
+    params: Query<PaginationParams>,
+    filter: Query<StructuresParams>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(USER_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let page = params.page.unwrap_or(1).max(1);
+    let size = params.size.unwrap_or(16).clamp(1, 64);
+    let sort = filter.sort.unwrap_or_default();
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let viewer = authenticate(&req, &mut tx).await;
+    let own = viewer == Some(id);
+
+    if !may_view(viewer, id, ProfileSection::Structures, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let total = sqlx::query_scalar!(
+        r"
+        SELECT COUNT(*)
+        FROM structures s
+        JOIN structure_creators c ON c.structure = s.id
+        WHERE c.creator = $1
+            AND ($2 OR (s.visibility = 'public' AND s.published <= NOW()))
+            AND ($3::TEXT IS NULL OR s.license = $3)
+        ",
+        id,
+        own,
+        filter.license,
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let structures = sqlx::query_as!(
+        StructurePreview,
+        r"
+        SELECT
+            s.id,
+            s.name,
+            si.url as image,
+            COALESCE(u.slug, u.name) as creator,
+            s.ratings,
+            s.rating_sum::DOUBLE PRECISION / NULLIF(s.ratings, 0) AS rating,
+            s.rating_score
+        FROM structures s
+        JOIN structure_creators c ON c.structure = s.id
+        LEFT JOIN LATERAL (
+            SELECT COALESCE(v.url, i.url) AS url
+            FROM structure_images i
+            LEFT JOIN structure_image_variants v ON v.image = i.id AND v.size = 'card' AND v.format = 'jpeg'
+            WHERE i.structure = s.id
+            ORDER BY i.id
             LIMIT 1
         ) si ON true
         LEFT JOIN LATERAL (
             SELECT creator
             FROM structure_creators
             WHERE structure = s.id
-            ORDER BY id
+            ORDER BY position, id
             LIMIT 1
         ) sc ON true
         LEFT JOIN users u ON u.id = sc.creator
+        LEFT JOIN LATERAL (
+            SELECT SUM(downloads) AS downloads
+            FROM structure_downloads_daily
+            WHERE structure = s.id
+        ) sd ON true
+        WHERE c.creator = $5
+            AND ($6 OR (s.visibility = 'public' AND s.published <= NOW()))
+            AND ($4::TEXT IS NULL OR s.license = $4)
+        ORDER BY
+            CASE WHEN $3 = 'downloads' THEN sd.downloads END DESC NULLS LAST,
+            CASE WHEN $3 = 'top_rated' THEN s.rating_score END DESC NULLS LAST,
+            s.id
         LIMIT $1
         OFFSET $2
         ",
         size,
         (page - 1) * size,
+        sort.as_str(),
+        filter.license,
+        id,
+        own,
     )
         .fetch_all(&mut *tx)
         .await
@@ -130,3 +497,355 @@ pub async fn get_structures(params: Query<PaginationParams>, postgre: Data<PgPoo
         },
     })
 }
+
+/// Public structures ranked by their most recently computed trending score.
+#[get("/structures/trending")]
+pub async fn get_trending_structures(
+    params: Query<PaginationParams>,
+    filter: Query<TrendingParams>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let page = params.page.unwrap_or(1).max(1);
+    let size = params.size.unwrap_or(16).clamp(1, 64);
+    let window = filter.window.unwrap_or_default();
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let total = sqlx::query_scalar!(
+        r"
+        SELECT COUNT(*)
+        FROM structure_trending t
+        JOIN structures s ON s.id = t.structure
+        WHERE t.period = $1 AND s.visibility = 'public' AND s.published <= NOW()
+        ",
+        window.as_str(),
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let structures = sqlx::query_as!(
+        StructurePreview,
+        r"
+        SELECT
+            s.id,
+            s.name,
+            si.url as image,
+            COALESCE(u.slug, u.name) as creator,
+            s.ratings,
+            s.rating_sum::DOUBLE PRECISION / NULLIF(s.ratings, 0) AS rating,
+            s.rating_score
+        FROM structure_trending t
+        JOIN structures s ON s.id = t.structure
+        LEFT JOIN LATERAL (
+            SELECT COALESCE(v.url, i.url) AS url
+            FROM structure_images i
+            LEFT JOIN structure_image_variants v ON v.image = i.id AND v.size = 'card' AND v.format = 'jpeg'
+            WHERE i.structure = s.id
+            ORDER BY i.id
+            LIMIT 1
+        ) si ON true
+        LEFT JOIN LATERAL (
+            SELECT creator
+            FROM structure_creators
+            WHERE structure = s.id
+            ORDER BY position, id
+            LIMIT 1
+        ) sc ON true
+        LEFT JOIN users u ON u.id = sc.creator
+        WHERE t.period = $3 AND s.visibility = 'public' AND s.published <= NOW()
+        ORDER BY t.score DESC, s.id
+        LIMIT $1
+        OFFSET $2
+        ",
+        size,
+        (page - 1) * size,
+        window.as_str(),
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(StructuresResponse {
+        structures,
+        pagination: Pagination {
+            page,
+            size,
+            total: (total + size - 1) / size,
+        },
+    })
+}
+
+#[get("/structures/{id}/files/{file}/download")]
+pub async fn download_structure_file(
+    req: HttpRequest,
+    path: Path<(String, String)>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let (id, file) = path.into_inner();
+
+    let (id, file) = match (decode_sqids(STRUCTURE_FLAG, &id), decode_sqids(FILE_FLAG, &file)) {
+        (Ok(id), Ok(file)) => (id, file),
+        _ => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let record = sqlx::query!(
+        r"SELECT url, blob FROM structure_files WHERE id = $1 AND structure = $2",
+        file,
+        id,
+    )
+        .fetch_optional(&mut *tx)
+        .await
+        .unwrap();
+
+    let record = match record {
+        Some(record) => record,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::NotFound().finish();
+        }
+    };
+
+    let user = authenticate(&req, &mut tx).await;
+
+    if !is_visible(id, user, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::NotFound().finish();
+    }
+
+    let ip = client_ip(&req);
+
+    // Rows older than a day are purged by `tasks::downloads`, but may linger until it runs.
+    let duplicated = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1
+            FROM structure_downloads
+            WHERE file = $1 AND ("user" = $2 OR ip = $3) AND created >= NOW() - INTERVAL '1 day'
+        )
+        "#,
+        file,
+        user,
+        ip,
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap()
+        .unwrap();
+
+    if !duplicated {
+        sqlx::query!(
+            r#"INSERT INTO structure_downloads (file, "user", ip) VALUES ($1, $2, $3)"#,
+            file,
+            user,
+            ip,
+        )
+            .execute(&mut *tx)
+            .await
+            .expect("failed to insert download");
+
+        sqlx::query!(
+            r"
+            INSERT INTO structure_downloads_daily (structure, day, downloads)
+            VALUES ($1, CURRENT_DATE, 1)
+            ON CONFLICT (structure, day)
+            DO UPDATE SET downloads = structure_downloads_daily.downloads + 1
+            ",
+            id,
+        )
+            .execute(&mut *tx)
+            .await
+            .expect("failed to count download");
+    }
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    let mut response = HttpResponse::Found();
+    response.insert_header((header::LOCATION, record.url));
+
+    if let Some(hash) = record.blob {
+        if let Ok(digest) = hex::decode(&hash) {
+            response.insert_header(("Digest", format!("sha-256={}", BASE64_STANDARD.encode(digest))));
+        }
+        response.insert_header((header::ETAG, format!("\"{}\"", hash)));
+    }
+
+    response.finish()
+}
+
+#[post("/structures/{id}/publish")]
+pub async fn publish_structure(
+    req: HttpRequest,
+    id: Path<String>,
+    publish: Json<StructurePublish>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    if publish.scheduled.is_some_and(|s| s <= Utc::now().naive_utc()) {
+        return HttpResponse::BadRequest().json(StructureErrorResponse {
+            reason: StructureError::InvalidSchedule,
+        });
+    }
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if !is_maintainer(id, user, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().finish();
+    }
+
+    match publish.scheduled {
+        Some(scheduled) => {
+            sqlx::query!(
+                r"UPDATE structures SET scheduled = $2 WHERE id = $1",
+                id,
+                scheduled
+            )
+                .execute(&mut *tx)
+                .await
+                .expect("failed to schedule structure");
+        }
+        None => {
+            sqlx::query!(
+                r"UPDATE structures SET visibility = 'public', published = COALESCE(published, NOW()), scheduled = NULL WHERE id = $1",
+                id
+            )
+                .execute(&mut *tx)
+                .await
+                .expect("failed to publish structure");
+        }
+    }
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
+
+#[put("/structures/{id}/visibility")]
+pub async fn set_structure_visibility(
+    req: HttpRequest,
+    id: Path<String>,
+    visibility: Json<StructureVisibility>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    // Going public goes through the publish action so it gets a publish time.
+    let visibility = match visibility.visibility {
+        Some(visibility) if visibility != Visibility::Public => visibility,
+        _ => return HttpResponse::BadRequest().json(StructureErrorResponse {
+            reason: StructureError::InvalidVisibility,
+        }),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if !is_maintainer(id, user, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().finish();
+    }
+
+    sqlx::query!(
+        r"UPDATE structures SET visibility = $2, scheduled = NULL WHERE id = $1",
+        id,
+        visibility.as_str()
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to update visibility");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
+
+#[put("/structures/{id}/license")]
+pub async fn set_structure_license(
+    req: HttpRequest,
+    id: Path<String>,
+    update: Json<LicenseUpdate>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let text = update.text.as_deref().map(str::trim).filter(|t| !t.is_empty());
+    if !is_valid_license(update.license.as_deref(), text) {
+        return HttpResponse::BadRequest().json(StructureErrorResponse {
+            reason: StructureError::InvalidLicense,
+        });
+    }
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if !is_maintainer(id, user, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().finish();
+    }
+
+    sqlx::query!(
+        r"
+        UPDATE structures
+        SET license = $2, license_text = $3, requires_attribution = $4, allows_commercial_use = $5
+        WHERE id = $1
+        ",
+        id,
+        update.license,
+        text,
+        update.requires_attribution.unwrap_or(false),
+        update.allows_commercial_use.unwrap_or(false)
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to update license");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
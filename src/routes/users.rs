@@ -1,73 +1,759 @@
-use actix_web::{get, HttpResponse, Responder};
-use actix_web::web::{Data, Path};
-use sqlx::PgPool;
+use actix_web::{delete, get, patch, put, HttpRequest, HttpResponse, Responder};
+use actix_web::web::{Bytes, Data, Json, Path, Query};
+use chrono::{Duration, Utc};
+use image::ImageFormat;
+use lazy_static::lazy_static;
+use regex::Regex;
+use sqlx::{PgConnection, PgPool};
 use destru::decode_sqids;
-use crate::models::ids::USER_FLAG;
-use crate::models::responses::UserResponse;
-use crate::models::users::User;
+use crate::markdown;
+use crate::models::badges::{Badge, UserBadge};
+use crate::models::files::FileVariant;
+use crate::models::ids::{UserID, USER_FLAG};
+use crate::models::pagination::{Pagination, PaginationParams};
+use crate::models::responses::{ImageError, ImageErrorResponse, PrivacyResponse, UserError, UserErrorResponse, UserHandleResponse, UserResponse, UsersResponse};
+use crate::models::users::{DbUser, PrivacySettings, PrivacyUpdate, User, UserSort, UserSummary, UserUpdate, UsersParams};
+use crate::routes::auths::{authenticate, client_ip, is_reserved, is_staff, NAME_REGEX};
+use crate::routes::limits::RateLimiter;
+use crate::routes::policy::{may_view, ProfileSection};
+use crate::storage::blobs;
+use crate::tasks::images::process_avatar;
 
+const MAX_BIO_LENGTH: usize = 2000;
+const MAX_AVATAR_SIZE: usize = 4 * 1024 * 1024;
+
+lazy_static! {
+    static ref SLUG_REGEX: Regex = Regex::new(r"^[a-z0-9][a-z0-9-]{1,30}[a-z0-9]$").unwrap();
+    static ref ANONYMOUS_LISTINGS: RateLimiter = RateLimiter::new(std::time::Duration::from_secs(60), 30);
+}
+
+/// How long after changing their name or slug a user has to wait to change either again.
+fn rename_cooldown() -> Duration {
+    Duration::days(30)
+}
+
+async fn get_avatars(user: i64, conn: &mut PgConnection) -> Vec<FileVariant> {
+    sqlx::query_as!(
+        FileVariant,
+        r#"SELECT size, format, url, width, height FROM user_avatar_variants WHERE "user" = $1 ORDER BY width, format"#,
+        user
+    )
+        .fetch_all(conn)
+        .await
+        .unwrap()
+}
+
+async fn get_badges(user: i64, conn: &mut PgConnection) -> Vec<UserBadge> {
+    sqlx::query!(
+        r#"SELECT badge, awarded FROM user_badges WHERE "user" = $1 ORDER BY awarded, badge"#,
+        user
+    )
+        .fetch_all(conn)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|record| UserBadge {
+            badge: Badge::parse(&record.badge),
+            awarded: record.awarded,
+        })
+        .collect()
+}
+
+async fn fetch_user(id: i64, conn: &mut PgConnection) -> Option<User> {
+    let user = sqlx::query_as!(
+        DbUser,
+        r#"
+        SELECT
+            u.id,
+            u.name,
+            u.avatar,
+            u.slug,
+            u.bio,
+            u.bio_html,
+            u.private,
+            u.created,
+            st.structures AS "structures!",
+            st.downloads AS "downloads!",
+            st.ratings AS "ratings!",
+            (SELECT COUNT(*) FROM user_follows WHERE followee = u.id) AS "followers!",
+            (SELECT COUNT(*) FROM user_follows WHERE follower = u.id) AS "following!"
+        FROM users u
+        LEFT JOIN LATERAL (
+            SELECT
+                COUNT(*) AS structures,
+                COALESCE(SUM(sd.downloads), 0)::BIGINT AS downloads,
+                COALESCE(SUM(s.ratings), 0)::BIGINT AS ratings
+            FROM structure_creators c
+            JOIN structures s ON s.id = c.structure
+            LEFT JOIN LATERAL (
+                SELECT SUM(downloads) AS downloads
+                FROM structure_downloads_daily
+                WHERE structure = s.id
+            ) sd ON true
+            WHERE c.creator = u.id AND s.visibility = 'public' AND s.published <= NOW()
+        ) st ON true
+        WHERE u.id = $1
+        "#,
+        id
+    )
+        .fetch_optional(&mut *conn)
+        .await
+        .unwrap()?;
+
+    let avatars = get_avatars(id, conn).await;
+    let badges = get_badges(id, conn).await;
+
+    Some(user.into_user(avatars, badges))
+}
+
+/// A user as `viewer` may see them.
+async fn fetch_profile(id: i64, viewer: Option<i64>, conn: &mut PgConnection) -> Option<User> {
+    let mut user = fetch_user(id, conn).await?;
+
+    if !may_view(viewer, id, ProfileSection::Profile, conn).await {
+        return Some(user.into_private());
+    }
+
+    // The counts would give away the size of lists the viewer isn't allowed to see.
+    let followers = may_view(viewer, id, ProfileSection::Followers, conn).await;
+    let following = may_view(viewer, id, ProfileSection::Activity, conn).await;
+    if let Some(stats) = &mut user.stats {
+        if !followers {
+            stats.followers = None;
+        }
+        if !following {
+            stats.following = None;
+        }
+    }
+
+    Some(user)
+}
+
+/// Directory of non-private users, optionally searched by name or slug, without anyone the caller muted.
+///
+/// Signed-in callers are unrestricted; anonymous ones are rate limited per address.
 #[get("/users")]
-pub async fn get_users() -> impl Responder {
-    HttpResponse::Unauthorized().finish()
+pub async fn get_users(
+    req: HttpRequest,
+    params: Query<PaginationParams>,
+    filter: Query<UsersParams>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let page = params.page.unwrap_or(1).max(1);
+    let size = params.size.unwrap_or(16).clamp(1, 64);
+
+    let query = filter.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+    let prefix = query.map(|q| format!("{}%", q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")));
+    let sort = match (filter.sort, query) {
+        (Some(UserSort::Relevance), None) | (None, None) => UserSort::Joined,
+        (Some(sort), _) => sort,
+        (None, Some(_)) => UserSort::Relevance,
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let viewer = authenticate(&req, &mut tx).await;
+
+    let staff = match viewer {
+        Some(user) => is_staff(user, &mut tx).await,
+        None => {
+            let ip = client_ip(&req).unwrap_or_default();
+            if !ANONYMOUS_LISTINGS.check(&ip) {
+                tx.commit().await.expect("failed to commit transaction");
+
+                return HttpResponse::TooManyRequests().finish();
+            }
+
+            false
+        }
+    };
+
+    let total = sqlx::query_scalar!(
+        r"
+        SELECT COUNT(*)
+        FROM users u
+        WHERE ($3 OR NOT u.private)
+            AND ($1::TEXT IS NULL OR u.name ILIKE $2 OR u.slug ILIKE $2 OR u.name % $1 OR u.slug % $1)
+            AND ($4::BIGINT IS NULL OR NOT is_muted($4, u.id))
+        ",
+        query,
+        prefix,
+        staff,
+        viewer,
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let users = sqlx::query!(
+        r#"
+        SELECT u.id, u.name, u.avatar, u.slug, u.created, sc.structures AS "structures!"
+        FROM users u
+        LEFT JOIN LATERAL (
+            SELECT COUNT(*) AS structures
+            FROM structure_creators c
+            JOIN structures s ON s.id = c.structure
+            WHERE c.creator = u.id AND s.visibility = 'public' AND s.published <= NOW()
+        ) sc ON true
+        WHERE ($5 OR NOT u.private)
+            AND ($3::TEXT IS NULL OR u.name ILIKE $4 OR u.slug ILIKE $4 OR u.name % $3 OR u.slug % $3)
+            AND ($7::BIGINT IS NULL OR NOT is_muted($7, u.id))
+        ORDER BY
+            CASE WHEN $6 = 'relevance' THEN COALESCE(u.name ILIKE $4 OR u.slug ILIKE $4, false) END DESC NULLS LAST,
+            CASE WHEN $6 = 'relevance' THEN GREATEST(similarity(u.name, $3), similarity(u.slug, $3)) END DESC NULLS LAST,
+            CASE WHEN $6 = 'structures' THEN sc.structures END DESC NULLS LAST,
+            u.created DESC,
+            u.id DESC
+        LIMIT $1
+        OFFSET $2
+        "#,
+        size,
+        (page - 1) * size,
+        query,
+        prefix,
+        staff,
+        sort.as_str(),
+        viewer,
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|record| UserSummary {
+            id: UserID::from(record.id),
+            name: record.name,
+            avatar: record.avatar,
+            slug: record.slug,
+            structures: record.structures,
+            created: Some(record.created),
+        })
+        .collect();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(UsersResponse {
+        users,
+        pagination: Pagination {
+            page,
+            size,
+            total: (total + size - 1) / size,
+        },
+    })
 }
 
-async fn get_user_by_id_response(id: &str, postgre: Data<PgPool>) -> HttpResponse {
+async fn get_user_by_id_response(req: &HttpRequest, id: &str, postgre: Data<PgPool>) -> HttpResponse {
     match decode_sqids(USER_FLAG, id) {
         Ok(id) => {
             let mut tx = postgre.begin().await.unwrap();
 
-            let user = sqlx::query_as!(
-                User,
-                r"SELECT id, name, avatar, slug, bio FROM users WHERE id = $1",
-                id
-            )
-                .fetch_one(&mut *tx)
-                .await;
+            let viewer = authenticate(req, &mut tx).await;
+            let user = fetch_profile(id, viewer, &mut tx).await;
 
             tx.commit().await.expect("failed to commit transaction");
 
             match user {
-                Ok(user) => HttpResponse::Ok().json(UserResponse { user }),
-                Err(e) => {
-                    println!("{:?}", e);
-                    HttpResponse::NotFound().finish()
-                },
+                Some(user) => HttpResponse::Ok().json(UserResponse { user }),
+                None => HttpResponse::NotFound().finish(),
             }
         }
         Err(_) => HttpResponse::NotFound().finish(),
     }
 }
 
-async fn get_user_by_name_response(name: &str, postgre: Data<PgPool>) -> HttpResponse {
+async fn get_user_by_name_response(req: &HttpRequest, name: &str, postgre: Data<PgPool>) -> HttpResponse {
     let mut tx = postgre.begin().await.unwrap();
 
-    let user = sqlx::query_as!(
-        User,
-        r"SELECT id, name, avatar, slug, bio FROM users WHERE name = $1",
+    let viewer = authenticate(req, &mut tx).await;
+
+    // A current name always wins over someone else's old one.
+    let id = sqlx::query_scalar!(
+        r#"
+        SELECT id
+        FROM users
+        WHERE name = $1 OR id = (SELECT "user" FROM user_names WHERE name = $1)
+        ORDER BY name = $1 DESC
+        LIMIT 1
+        "#,
         name
     )
-        .fetch_one(&mut *tx)
-        .await;
+        .fetch_optional(&mut *tx)
+        .await
+        .unwrap();
+
+    let user = match id {
+        Some(id) => fetch_profile(id, viewer, &mut tx).await,
+        None => None,
+    };
 
     tx.commit().await.expect("failed to commit transaction");
 
     match user {
-        Ok(user) => HttpResponse::Ok().json(UserResponse { user }),
-        Err(_) => HttpResponse::NotFound().finish(),
+        Some(user) => HttpResponse::Ok().json(UserResponse { user }),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+async fn get_user_by_slug_response(req: &HttpRequest, slug: &str, postgre: Data<PgPool>) -> HttpResponse {
+    let mut tx = postgre.begin().await.unwrap();
+
+    let viewer = authenticate(req, &mut tx).await;
+
+    let id = sqlx::query_scalar!(
+        r"SELECT id FROM users WHERE slug = LOWER($1)",
+        slug
+    )
+        .fetch_optional(&mut *tx)
+        .await
+        .unwrap();
+
+    let user = match id {
+        Some(id) => fetch_profile(id, viewer, &mut tx).await,
+        None => None,
+    };
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    match user {
+        Some(user) => HttpResponse::Ok().json(UserResponse { user }),
+        None => HttpResponse::NotFound().finish(),
     }
 }
 
+/// Resolves a handle as shown on profiles and structure cards.
+///
+/// Slugs take precedence over names, and names over names a user has since given up. The
+/// response carries the canonical handle so clients can redirect stale URLs to it.
+#[get("/users/@{handle}")]
+pub async fn get_user_by_handle(req: HttpRequest, handle: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let handle = handle.into_inner();
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let viewer = authenticate(&req, &mut tx).await;
+
+    let resolved = sqlx::query!(
+        r#"
+        SELECT id, COALESCE(slug, name) AS "handle!"
+        FROM users
+        WHERE slug = LOWER($1) OR name = $1 OR id = (SELECT "user" FROM user_names WHERE name = $1)
+        ORDER BY slug = LOWER($1) DESC NULLS LAST, name = $1 DESC
+        LIMIT 1
+        "#,
+        handle
+    )
+        .fetch_optional(&mut *tx)
+        .await
+        .unwrap();
+
+    let resolved = match resolved {
+        Some(resolved) => resolved,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::NotFound().finish();
+        }
+    };
+
+    let user = fetch_profile(resolved.id, viewer, &mut tx).await.expect("resolved user exists");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(UserHandleResponse {
+        user,
+        redirect: resolved.handle != handle,
+        handle: resolved.handle,
+    })
+}
+
 #[get("/users/{id}")]
-pub async fn get_user(id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
-    get_user_by_id_response(id.as_str(), postgre).await
+pub async fn get_user(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    get_user_by_id_response(&req, id.as_str(), postgre).await
 }
 
 #[get("/users/by/{key}/{value}")]
-pub async fn get_user_by(path: Path<(String, String)>, postgre: Data<PgPool>) -> impl Responder {
+pub async fn get_user_by(req: HttpRequest, path: Path<(String, String)>, postgre: Data<PgPool>) -> impl Responder {
     let (key, value) = path.into_inner();
     match key.as_str() {
-        "id" => get_user_by_id_response(value.as_str(), postgre).await,
-        "name" => get_user_by_name_response(value.as_str(), postgre).await,
+        "id" => get_user_by_id_response(&req, value.as_str(), postgre).await,
+        "name" => get_user_by_name_response(&req, value.as_str(), postgre).await,
+        "slug" => get_user_by_slug_response(&req, value.as_str(), postgre).await,
         _ => HttpResponse::NotFound().finish(),
     }
 }
+
+#[patch("/users/me")]
+pub async fn update_me(req: HttpRequest, update: Json<UserUpdate>, postgre: Data<PgPool>) -> impl Responder {
+    let name = update.name.as_deref().map(str::trim);
+    if let Some(name) = name {
+        if !NAME_REGEX.is_match(name) {
+            return HttpResponse::BadRequest().json(UserErrorResponse {
+                reason: UserError::InvalidName,
+            });
+        }
+
+        if is_reserved(name) {
+            return HttpResponse::BadRequest().json(UserErrorResponse {
+                reason: UserError::ReservedName,
+            });
+        }
+    }
+
+    let slug = update.slug.as_deref().map(str::trim).map(|s| Some(s).filter(|s| !s.is_empty()));
+    if let Some(Some(slug)) = slug {
+        if !SLUG_REGEX.is_match(slug) {
+            return HttpResponse::BadRequest().json(UserErrorResponse {
+                reason: UserError::InvalidSlug,
+            });
+        }
+
+        if is_reserved(slug) {
+            return HttpResponse::BadRequest().json(UserErrorResponse {
+                reason: UserError::ReservedName,
+            });
+        }
+    }
+
+    let bio = update.bio.as_deref().map(str::trim).map(|b| Some(b).filter(|b| !b.is_empty()));
+    if bio.flatten().is_some_and(|b| b.chars().count() > MAX_BIO_LENGTH) {
+        return HttpResponse::BadRequest().json(UserErrorResponse {
+            reason: UserError::BioTooLong,
+        });
+    }
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let current = sqlx::query!(
+        r"SELECT name, slug, bio, renamed FROM users WHERE id = $1",
+        user
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap();
+
+    let name = name.filter(|n| *n != current.name);
+    let slug = slug.filter(|s| *s != current.slug.as_deref());
+
+    if name.is_some() || slug.is_some() {
+        let cutoff = (Utc::now() - rename_cooldown()).naive_utc();
+        if current.renamed.is_some_and(|renamed| renamed > cutoff) {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::TooManyRequests().json(UserErrorResponse {
+                reason: UserError::RenameTooSoon,
+            });
+        }
+    }
+
+    // Names and slugs share one namespace so that a handle resolves to a single user.
+    if let Some(name) = name {
+        let exists = sqlx::query_scalar!(
+            r"SELECT EXISTS(SELECT 1 FROM users WHERE id <> $2 AND (name = $1 OR slug = LOWER($1)))",
+            name,
+            user
+        )
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap()
+            .unwrap();
+
+        if exists {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::BadRequest().json(UserErrorResponse {
+                reason: UserError::NameExists,
+            });
+        }
+    }
+
+    if let Some(Some(slug)) = slug {
+        let exists = sqlx::query_scalar!(
+            r"SELECT EXISTS(SELECT 1 FROM users WHERE id <> $2 AND (slug = $1 OR LOWER(name) = $1))",
+            slug,
+            user
+        )
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap()
+            .unwrap();
+
+        if exists {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::BadRequest().json(UserErrorResponse {
+                reason: UserError::SlugExists,
+            });
+        }
+    }
+
+    if let Some(name) = name {
+        sqlx::query!(
+            r"DELETE FROM user_names WHERE name = $1",
+            name
+        )
+            .execute(&mut *tx)
+            .await
+            .expect("failed to delete name history");
+
+        sqlx::query!(
+            r#"
+            INSERT INTO user_names (name, "user") VALUES ($1, $2)
+            ON CONFLICT (name) DO UPDATE SET "user" = EXCLUDED."user", created = NOW()
+            "#,
+            current.name,
+            user
+        )
+            .execute(&mut *tx)
+            .await
+            .expect("failed to insert name history");
+    }
+
+    let (bio, bio_html) = match bio {
+        Some(bio) => (bio.map(str::to_owned), bio.map(markdown::render)),
+        None => (current.bio, None),
+    };
+
+    sqlx::query!(
+        r"
+        UPDATE users
+        SET name = $2,
+            slug = $3,
+            bio = $4,
+            bio_html = CASE WHEN $5 THEN $6 ELSE bio_html END,
+            renamed = CASE WHEN $7 THEN NOW() ELSE renamed END
+        WHERE id = $1
+        ",
+        user,
+        name.unwrap_or(&current.name),
+        slug.unwrap_or(current.slug.as_deref()),
+        bio,
+        update.bio.is_some(),
+        bio_html,
+        name.is_some() || slug.is_some()
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to update user");
+
+    let user = fetch_user(user, &mut tx).await.expect("authenticated user exists");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(UserResponse { user })
+}
+
+/// Replaces the caller's avatar with square crops of the uploaded image.
+#[put("/users/me/avatar")]
+pub async fn upload_avatar(req: HttpRequest, body: Bytes, postgre: Data<PgPool>) -> impl Responder {
+    if body.len() > MAX_AVATAR_SIZE {
+        return HttpResponse::PayloadTooLarge().json(ImageErrorResponse {
+            reason: ImageError::TooLarge,
+        });
+    }
+
+    match image::guess_format(&body) {
+        Ok(ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP | ImageFormat::Gif) => {}
+        _ => return HttpResponse::BadRequest().json(ImageErrorResponse {
+            reason: ImageError::UnsupportedFormat,
+        }),
+    }
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let variants = match actix_rt::task::spawn_blocking(move || process_avatar(&body)).await {
+        Ok(Ok(variants)) => variants,
+        Ok(Err(e)) => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            log::warn!("failed to process avatar: {}", e);
+
+            return HttpResponse::BadRequest().json(ImageErrorResponse {
+                reason: ImageError::UnsupportedFormat,
+            });
+        }
+        Err(e) => {
+            log::error!("failed to process avatar: {:?}", e);
+
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    sqlx::query!(
+        r#"DELETE FROM user_avatar_variants WHERE "user" = $1"#,
+        user
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to delete avatar variants");
+
+    let mut avatar = None;
+    for variant in variants {
+        let blob = match blobs::store(variant.data, &mut tx).await {
+            Ok(blob) => blob,
+            Err(e) => {
+                log::error!("failed to store avatar: {:?}", e);
+
+                return HttpResponse::InternalServerError().finish();
+            }
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO user_avatar_variants ("user", size, format, url, blob, width, height)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            user,
+            variant.size,
+            variant.format,
+            blob.url,
+            blob.hash,
+            variant.width as i32,
+            variant.height as i32
+        )
+            .execute(&mut *tx)
+            .await
+            .expect("failed to insert avatar variant");
+
+        if variant.size == "large" && variant.format == "jpeg" {
+            avatar = Some(blob.url);
+        }
+    }
+
+    sqlx::query!(
+        r"UPDATE users SET avatar = $2 WHERE id = $1",
+        user,
+        avatar
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to update avatar");
+
+    let user = fetch_user(user, &mut tx).await.expect("authenticated user exists");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(UserResponse { user })
+}
+
+#[delete("/users/me/avatar")]
+pub async fn delete_avatar(req: HttpRequest, postgre: Data<PgPool>) -> impl Responder {
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    sqlx::query!(
+        r#"DELETE FROM user_avatar_variants WHERE "user" = $1"#,
+        user
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to delete avatar variants");
+
+    sqlx::query!(
+        r"UPDATE users SET avatar = NULL WHERE id = $1",
+        user
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to update avatar");
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
+
+async fn get_privacy(user: i64, conn: &mut PgConnection) -> PrivacySettings {
+    sqlx::query_as!(
+        PrivacySettings,
+        r"SELECT private, hide_activity, hide_collections, hide_followers FROM users WHERE id = $1",
+        user
+    )
+        .fetch_one(conn)
+        .await
+        .unwrap()
+}
+
+#[get("/users/me/privacy")]
+pub async fn get_my_privacy(req: HttpRequest, postgre: Data<PgPool>) -> impl Responder {
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    let privacy = get_privacy(user, &mut tx).await;
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(PrivacyResponse { privacy })
+}
+
+/// Changes the given privacy settings, leaving the others as they are.
+#[patch("/users/me/privacy")]
+pub async fn update_my_privacy(req: HttpRequest, update: Json<PrivacyUpdate>, postgre: Data<PgPool>) -> impl Responder {
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    sqlx::query!(
+        r"
+        UPDATE users
+        SET private = COALESCE($2, private),
+            hide_activity = COALESCE($3, hide_activity),
+            hide_collections = COALESCE($4, hide_collections),
+            hide_followers = COALESCE($5, hide_followers)
+        WHERE id = $1
+        ",
+        user,
+        update.private,
+        update.hide_activity,
+        update.hide_collections,
+        update.hide_followers
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to update privacy settings");
+
+    let privacy = get_privacy(user, &mut tx).await;
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(PrivacyResponse { privacy })
+}
@@ -0,0 +1,364 @@
+use actix_web::{get, post, HttpRequest, HttpResponse, Responder};
+use actix_web::web::{Data, Json, Path};
+use lazy_static::lazy_static;
+use regex::Regex;
+use sqlx::{PgConnection, PgPool};
+use destru::decode_sqids;
+use crate::markdown;
+use crate::models::files::{DbFile, File};
+use crate::models::ids::STRUCTURE_FLAG;
+use crate::models::responses::{VersionError, VersionErrorResponse, VersionResponse, VersionsResponse};
+use crate::models::versions::{Version, VersionCreate};
+use crate::routes::auths::authenticate;
+use crate::routes::structures::{is_maintainer, is_visible};
+
+lazy_static! {
+    static ref LABEL_REGEX: Regex = Regex::new(r"^[0-9a-zA-Z._+-]{1,32}$").unwrap();
+}
+
+const MAX_CHANGELOG_LENGTH: usize = 10000;
+const MAX_DESCRIPTION_LENGTH: usize = 100000;
+
+pub fn is_valid_label(label: &str) -> bool {
+    LABEL_REGEX.is_match(label)
+}
+
+/// Creates a version on top of the latest one and makes it the latest.
+///
+/// Files are carried forward from the latest version unless a subset is picked explicitly.
+/// Returns `None` if the label is already taken.
+pub async fn insert_version(
+    structure: i64,
+    label: &str,
+    changelog: &str,
+    description: Option<String>,
+    files: Option<Vec<i64>>,
+    conn: &mut PgConnection,
+) -> Option<i64> {
+    let label_exists = sqlx::query_scalar!(
+        r"SELECT EXISTS(SELECT 1 FROM structure_versions WHERE structure = $1 AND label = $2)",
+        structure,
+        label
+    )
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap()
+        .unwrap();
+
+    if label_exists {
+        return None;
+    }
+
+    let current = sqlx::query!(
+        r"SELECT latest, description FROM structures WHERE id = $1",
+        structure
+    )
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+
+    let description = description.unwrap_or(current.description);
+    let description_html = markdown::render(&description);
+
+    let version = sqlx::query_scalar!(
+        r"
+        INSERT INTO structure_versions (structure, label, changelog, description, description_html)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        ",
+        structure,
+        label,
+        changelog,
+        description,
+        description_html
+    )
+        .fetch_one(&mut *conn)
+        .await
+        .expect("failed to insert version");
+
+    match files {
+        Some(files) => {
+            sqlx::query!(
+                r"
+                INSERT INTO structure_files (structure, version, name, url, blob, format, metadata, created)
+                SELECT DISTINCT ON (url) structure, $2, name, url, blob, format, metadata, NOW()
+                FROM structure_files
+                WHERE structure = $1 AND id = ANY($3)
+                ",
+                structure,
+                version,
+                &files
+            )
+                .execute(&mut *conn)
+                .await
+                .expect("failed to copy files");
+        }
+        None => {
+            sqlx::query!(
+                r"
+                INSERT INTO structure_files (structure, version, name, url, blob, format, metadata, created)
+                SELECT structure, $2, name, url, blob, format, metadata, NOW()
+                FROM structure_files
+                WHERE version = $1
+                ",
+                current.latest,
+                version
+            )
+                .execute(&mut *conn)
+                .await
+                .expect("failed to copy files");
+        }
+    }
+
+    sqlx::query!(
+        r"UPDATE structures SET latest = $2, description = $3, description_html = $4 WHERE id = $1",
+        structure,
+        version,
+        description,
+        description_html
+    )
+        .execute(&mut *conn)
+        .await
+        .expect("failed to update latest version");
+
+    Some(version)
+}
+
+#[get("/structures/{id}/versions")]
+pub async fn get_versions(req: HttpRequest, id: Path<String>, postgre: Data<PgPool>) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = authenticate(&req, &mut tx).await;
+
+    if !is_visible(id, user, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::NotFound().finish();
+    }
+
+    let versions = sqlx::query_as!(
+        Version,
+        r#"
+        SELECT v.label, v.changelog, COALESCE(v.id = s.latest, false) AS "latest!", v.created
+        FROM structure_versions v
+        JOIN structures s ON s.id = v.structure
+        WHERE v.structure = $1
+        ORDER BY v.id DESC
+        "#,
+        id
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(VersionsResponse { versions })
+}
+
+#[get("/structures/{id}/versions/{label}")]
+pub async fn get_version(
+    req: HttpRequest,
+    path: Path<(String, String)>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let (id, label) = path.into_inner();
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = authenticate(&req, &mut tx).await;
+
+    if !is_visible(id, user, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::NotFound().finish();
+    }
+
+    let row = sqlx::query!(
+        r#"
+        SELECT v.id, v.label, v.changelog, v.description, v.description_html,
+            COALESCE(v.id = s.latest, false) AS "latest!", v.created
+        FROM structure_versions v
+        JOIN structures s ON s.id = v.structure
+        WHERE v.structure = $1 AND v.label = $2
+        "#,
+        id,
+        label
+    )
+        .fetch_optional(&mut *tx)
+        .await
+        .unwrap();
+
+    let record = match row {
+        Some(record) => record,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::NotFound().finish();
+        }
+    };
+
+    let files = sqlx::query_as!(
+        DbFile,
+        r"
+        SELECT f.id, f.name, f.url, b.hash AS checksum, b.size, f.format, f.metadata, f.created
+        FROM structure_files f
+        LEFT JOIN blobs b ON b.hash = f.blob
+        WHERE f.version = $1
+        ",
+        record.id
+    )
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(File::from)
+        .collect();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().json(VersionResponse {
+        version: Version {
+            label: record.label,
+            changelog: record.changelog,
+            latest: record.latest,
+            created: record.created,
+        },
+        description: record.description,
+        description_html: record.description_html.unwrap_or_default(),
+        files,
+    })
+}
+
+#[post("/structures/{id}/versions")]
+pub async fn create_version(
+    req: HttpRequest,
+    id: Path<String>,
+    create: Json<VersionCreate>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let label = create.label.clone().unwrap_or_default();
+    if !is_valid_label(&label) {
+        return HttpResponse::BadRequest().json(VersionErrorResponse {
+            reason: VersionError::InvalidLabel,
+        });
+    }
+
+    let changelog = create.changelog.clone().unwrap_or_default();
+    if changelog.chars().count() > MAX_CHANGELOG_LENGTH {
+        return HttpResponse::BadRequest().json(VersionErrorResponse {
+            reason: VersionError::InvalidChangelog,
+        });
+    }
+
+    if create.description.as_ref().is_some_and(|d| d.chars().count() > MAX_DESCRIPTION_LENGTH) {
+        return HttpResponse::BadRequest().json(VersionErrorResponse {
+            reason: VersionError::InvalidDescription,
+        });
+    }
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if !is_maintainer(id, user, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let version = insert_version(
+        id,
+        &label,
+        &changelog,
+        create.description.clone(),
+        create.files.as_ref().map(|files| files.iter().map(|f| f.0).collect()),
+        &mut tx,
+    ).await;
+
+    if version.is_none() {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::BadRequest().json(VersionErrorResponse {
+            reason: VersionError::LabelExists,
+        });
+    }
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    HttpResponse::Ok().finish()
+}
+
+#[post("/structures/{id}/versions/{label}/latest")]
+pub async fn mark_latest_version(
+    req: HttpRequest,
+    path: Path<(String, String)>,
+    postgre: Data<PgPool>,
+) -> impl Responder {
+    let (id, label) = path.into_inner();
+    let id = match decode_sqids(STRUCTURE_FLAG, id.as_str()) {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    let mut tx = postgre.begin().await.unwrap();
+
+    let user = match authenticate(&req, &mut tx).await {
+        Some(user) => user,
+        None => {
+            tx.commit().await.expect("failed to commit transaction");
+
+            return HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    if !is_maintainer(id, user, &mut tx).await {
+        tx.commit().await.expect("failed to commit transaction");
+
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let updated = sqlx::query!(
+        r"
+        UPDATE structures s
+        SET latest = v.id, description = v.description, description_html = v.description_html
+        FROM structure_versions v
+        WHERE s.id = $1 AND v.structure = s.id AND v.label = $2
+        ",
+        id,
+        label
+    )
+        .execute(&mut *tx)
+        .await
+        .expect("failed to update latest version")
+        .rows_affected();
+
+    tx.commit().await.expect("failed to commit transaction");
+
+    if updated == 0 {
+        HttpResponse::NotFound().finish()
+    } else {
+        HttpResponse::Ok().finish()
+    }
+}
@@ -0,0 +1,61 @@
+use std::io;
+use sha2::{Digest, Sha256};
+use sqlx::PgConnection;
+use thiserror::Error;
+use crate::storage::local;
+
+#[derive(Debug, Error)]
+pub enum BlobError {
+    #[error("存储错误: {0}")]
+    Storage(#[from] io::Error),
+    #[error("数据库错误: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+pub struct Blob {
+    pub hash: String,
+    pub url: String,
+}
+
+pub fn key(hash: &str) -> String {
+    format!("blobs/{}/{}", &hash[..2], hash)
+}
+
+/// Hex-encoded SHA-256 of the data.
+pub fn hash(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Stores data under its SHA-256 hash, writing it out only if no identical blob exists yet.
+///
+/// The blob starts unreferenced; inserting a row that points at it takes the reference.
+/// The file is written before the transaction commits, so a rollback leaves it for the collector to sweep.
+pub async fn store(data: Vec<u8>, conn: &mut PgConnection) -> Result<Blob, BlobError> {
+    let (hash, data) = actix_rt::task::spawn_blocking(move || (hash(&data), data))
+        .await
+        .map_err(io::Error::other)?;
+
+    // Locks the row, so a concurrent collection either finishes first or sees it fresh again.
+    let inserted = sqlx::query_scalar!(
+        r#"
+        INSERT INTO blobs (hash, size)
+        VALUES ($1, $2)
+        ON CONFLICT (hash) DO UPDATE SET orphaned = CASE WHEN blobs.refs = 0 THEN NOW() END
+        RETURNING (xmax = 0) AS "inserted!"
+        "#,
+        hash,
+        data.len() as i64
+    )
+        .fetch_one(&mut *conn)
+        .await?;
+
+    let key = key(&hash);
+    if inserted {
+        local::write(&key, data).await?;
+    }
+
+    Ok(Blob {
+        url: local::url(&key),
+        hash,
+    })
+}
@@ -0,0 +1,92 @@
+use std::io;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref STORAGE_PATH: PathBuf = PathBuf::from(
+        dotenvy::var("STORAGE_PATH").unwrap_or_else(|_| "storage".to_owned())
+    );
+
+    static ref STORAGE_URL: String = dotenvy::var("STORAGE_URL")
+        .expect("`STORAGE_URL` not in .env")
+        .trim_end_matches('/')
+        .to_owned();
+}
+
+/// Public URL a stored object is served from.
+pub fn url(key: &str) -> String {
+    format!("{}/{}", *STORAGE_URL, key)
+}
+
+/// Key of an object served from `url`, if it lives in this storage.
+pub fn key(url: &str) -> Option<&str> {
+    url.strip_prefix(STORAGE_URL.as_str())?.strip_prefix('/')
+}
+
+pub async fn write(key: &str, data: Vec<u8>) -> io::Result<()> {
+    let path = STORAGE_PATH.join(key);
+
+    actix_rt::task::spawn_blocking(move || {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, data)
+    })
+        .await
+        .map_err(io::Error::other)?
+}
+
+pub async fn read(key: &str) -> io::Result<Vec<u8>> {
+    let path = STORAGE_PATH.join(key);
+
+    actix_rt::task::spawn_blocking(move || std::fs::read(path))
+        .await
+        .map_err(io::Error::other)?
+}
+
+/// Keys of every object under a prefix, with when each was last written.
+pub async fn list(prefix: &str) -> io::Result<Vec<(String, SystemTime)>> {
+    let prefix = prefix.to_owned();
+
+    actix_rt::task::spawn_blocking(move || {
+        let mut objects = Vec::new();
+        let mut dirs = vec![prefix];
+        while let Some(dir) = dirs.pop() {
+            let entries = match std::fs::read_dir(STORAGE_PATH.join(&dir)) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+
+            for entry in entries {
+                let entry = entry?;
+                let key = format!("{}/{}", dir, entry.file_name().to_string_lossy());
+                let metadata = entry.metadata()?;
+                if metadata.is_dir() {
+                    dirs.push(key);
+                } else {
+                    objects.push((key, metadata.modified()?));
+                }
+            }
+        }
+        Ok(objects)
+    })
+        .await
+        .map_err(io::Error::other)?
+}
+
+pub async fn open(key: &str) -> io::Result<tokio::fs::File> {
+    tokio::fs::File::open(STORAGE_PATH.join(key)).await
+}
+
+pub async fn delete(key: &str) -> io::Result<()> {
+    let path = STORAGE_PATH.join(key);
+
+    actix_rt::task::spawn_blocking(move || match std::fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    })
+        .await
+        .map_err(io::Error::other)?
+}
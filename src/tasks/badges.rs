@@ -0,0 +1,90 @@
+use std::time::Duration;
+use sqlx::PgPool;
+use crate::models::badges::Badge;
+
+/// Awards every badge to the users who currently meet its rule.
+///
+/// Achievements are kept once earned, while the staff badge follows the user's current role.
+pub fn spawn(postgre: PgPool) {
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(60 * 60));
+
+        loop {
+            interval.tick().await;
+
+            for badge in Badge::ALL {
+                match award(badge, &postgre).await {
+                    Ok(awarded) if awarded > 0 => log::info!("awarded {} {} badges", awarded, badge.as_str()),
+                    Ok(_) => {}
+                    Err(e) => log::error!("failed to award {} badges: {:?}", badge.as_str(), e),
+                }
+            }
+        }
+    });
+}
+
+/// Awards a badge to everyone who qualifies, returning how many users newly received it.
+async fn award(badge: Badge, postgre: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = match badge {
+        Badge::FirstUpload => sqlx::query!(
+            r#"
+            INSERT INTO user_badges ("user", badge)
+            SELECT DISTINCT c.creator, $1
+            FROM structure_creators c
+            JOIN structures s ON s.id = c.structure
+            WHERE s.visibility = 'public' AND s.published <= NOW()
+            ON CONFLICT DO NOTHING
+            "#,
+            badge.as_str()
+        )
+            .execute(postgre)
+            .await?,
+        Badge::ThousandDownloads | Badge::TenThousandDownloads => sqlx::query!(
+            r#"
+            INSERT INTO user_badges ("user", badge)
+            SELECT c.creator, $1
+            FROM structure_creators c
+            JOIN structures s ON s.id = c.structure
+            JOIN structure_downloads_daily d ON d.structure = c.structure
+            WHERE s.visibility = 'public' AND s.published <= NOW()
+            GROUP BY c.creator
+            HAVING SUM(d.downloads)::BIGINT >= $2
+            ON CONFLICT DO NOTHING
+            "#,
+            badge.as_str(),
+            if badge == Badge::ThousandDownloads { 1_000 } else { 10_000 }
+        )
+            .execute(postgre)
+            .await?,
+        Badge::HundredFollowers => sqlx::query!(
+            r#"
+            INSERT INTO user_badges ("user", badge)
+            SELECT followee, $1
+            FROM user_follows
+            GROUP BY followee
+            HAVING COUNT(*) >= 100
+            ON CONFLICT DO NOTHING
+            "#,
+            badge.as_str()
+        )
+            .execute(postgre)
+            .await?,
+        Badge::Staff => {
+            sqlx::query!(
+                r#"DELETE FROM user_badges b USING users u WHERE u.id = b."user" AND b.badge = $1 AND NOT u.staff"#,
+                badge.as_str()
+            )
+                .execute(postgre)
+                .await?;
+
+            sqlx::query!(
+                r#"INSERT INTO user_badges ("user", badge) SELECT id, $1 FROM users WHERE staff ON CONFLICT DO NOTHING"#,
+                badge.as_str()
+            )
+                .execute(postgre)
+                .await?
+        }
+    };
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,119 @@
+use std::time::{Duration, SystemTime};
+use sqlx::PgPool;
+use crate::storage::blobs::{self, BlobError};
+use crate::storage::local;
+
+/// How long an unreferenced blob is kept, so in-flight uploads can still claim it.
+const GRACE_PERIOD_HOURS: i32 = 24;
+const BATCH_SIZE: i64 = 256;
+
+/// Deletes blobs that have been unreferenced for longer than the grace period,
+/// along with blob files whose row was rolled back.
+pub fn spawn(postgre: PgPool) {
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(60 * 60));
+
+        loop {
+            interval.tick().await;
+
+            loop {
+                match collect(&postgre).await {
+                    Ok(0) => break,
+                    Ok(collected) => log::info!("collected {} unreferenced blobs", collected),
+                    Err(e) => {
+                        log::error!("failed to collect unreferenced blobs: {:?}", e);
+                        break;
+                    }
+                }
+            }
+
+            match sweep(&postgre).await {
+                Ok(0) => {}
+                Ok(swept) => log::info!("swept {} stray blob files", swept),
+                Err(e) => log::error!("failed to sweep stray blob files: {:?}", e),
+            }
+        }
+    });
+}
+
+async fn collect(postgre: &PgPool) -> Result<usize, sqlx::Error> {
+    let mut tx = postgre.begin().await?;
+
+    // The rows stay locked until the files are gone, so a re-upload waits and stores it afresh.
+    let hashes = sqlx::query_scalar!(
+        r"
+        DELETE FROM blobs
+        WHERE hash IN (
+            SELECT hash
+            FROM blobs
+            WHERE refs = 0 AND orphaned < NOW() - MAKE_INTERVAL(hours => $1)
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING hash
+        ",
+        GRACE_PERIOD_HOURS,
+        BATCH_SIZE
+    )
+        .fetch_all(&mut *tx)
+        .await?;
+
+    for hash in &hashes {
+        if let Err(e) = local::delete(&blobs::key(hash)).await {
+            log::warn!("failed to delete blob {}: {:?}", hash, e);
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(hashes.len())
+}
+
+/// Deletes blob files with no row, which are left behind when the transaction that stored them rolls back.
+async fn sweep(postgre: &PgPool) -> Result<usize, BlobError> {
+    let cutoff = SystemTime::now() - Duration::from_secs(GRACE_PERIOD_HOURS as u64 * 60 * 60);
+
+    // Files written within the grace period may belong to a transaction that hasn't committed yet.
+    let hashes = local::list("blobs")
+        .await?
+        .into_iter()
+        .filter(|(_, modified)| *modified < cutoff)
+        .filter_map(|(key, _)| key.rsplit('/').next().map(str::to_owned))
+        .filter(|hash| hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+        .collect::<Vec<_>>();
+
+    let mut swept = 0;
+    for batch in hashes.chunks(BATCH_SIZE as usize) {
+        let mut tx = postgre.begin().await?;
+
+        // Claiming the missing rows makes a concurrent upload of the same data wait, then store it afresh.
+        let strays = sqlx::query_scalar!(
+            r"
+            INSERT INTO blobs (hash, size)
+            SELECT UNNEST($1::TEXT[]), 0
+            ON CONFLICT (hash) DO NOTHING
+            RETURNING hash
+            ",
+            batch
+        )
+            .fetch_all(&mut *tx)
+            .await?;
+
+        for hash in &strays {
+            local::delete(&blobs::key(hash)).await?;
+        }
+
+        sqlx::query!(
+            r"DELETE FROM blobs WHERE hash = ANY($1)",
+            &strays
+        )
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        swept += strays.len();
+    }
+
+    Ok(swept)
+}
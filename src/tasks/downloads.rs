@@ -0,0 +1,29 @@
+use std::time::Duration;
+use sqlx::PgPool;
+
+/// Purges download records once they're too old to deduplicate against.
+///
+/// Daily counts live in `structure_downloads_daily`, so nothing is lost.
+pub fn spawn(postgre: PgPool) {
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(60 * 60));
+
+        loop {
+            interval.tick().await;
+
+            let result = sqlx::query!(
+                r"DELETE FROM structure_downloads WHERE created < NOW() - INTERVAL '1 day'"
+            )
+                .execute(&postgre)
+                .await;
+
+            match result {
+                Ok(result) if result.rows_affected() > 0 => {
+                    log::info!("purged {} expired downloads", result.rows_affected());
+                }
+                Ok(_) => {}
+                Err(e) => log::error!("failed to purge expired downloads: {:?}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,335 @@
+use std::io::{self, Cursor};
+use std::time::{Duration, SystemTime};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageError, ImageReader};
+use sqlx::PgPool;
+use thiserror::Error;
+use crate::storage::blobs::{self, BlobError};
+use crate::storage::local;
+
+const MAX_ATTEMPTS: i32 = 3;
+/// Sources newer than this may belong to an upload whose transaction hasn't committed yet.
+const SWEEP_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
+const JPEG_QUALITY: u8 = 85;
+const WEBP_QUALITY: f32 = 80.0;
+
+/// Longest edge of each generated variant.
+const SIZES: [(&str, u32); 3] = [("thumbnail", 256), ("card", 640), ("full", 1920)];
+
+/// Edge of each square avatar variant.
+const AVATAR_SIZES: [(&str, u32); 3] = [("small", 64), ("medium", 128), ("large", 256)];
+
+#[derive(Debug, Error)]
+enum ProcessError {
+    #[error("图片错误: {0}")]
+    Image(#[from] ImageError),
+    #[error("Blurhash错误: {0}")]
+    Blurhash(#[from] blurhash::Error),
+    #[error("存储错误: {0}")]
+    Storage(#[from] io::Error),
+    #[error("数据库错误: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("文件错误: {0}")]
+    Blob(#[from] BlobError),
+}
+
+pub struct Variant {
+    pub size: &'static str,
+    pub format: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+struct Processed {
+    width: u32,
+    height: u32,
+    blurhash: String,
+    variants: Vec<Variant>,
+}
+
+pub fn upload_key(upload: i64) -> String {
+    format!("uploads/images/{}", upload)
+}
+
+/// Processes queued image uploads into resized, metadata-free variants,
+/// and sweeps sources whose upload was rolled back.
+pub fn spawn(postgre: PgPool) {
+    let sweeper = postgre.clone();
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(60 * 60));
+
+        loop {
+            interval.tick().await;
+
+            match sweep(&sweeper).await {
+                Ok(0) => {}
+                Ok(swept) => log::info!("swept {} stray upload sources", swept),
+                Err(e) => log::error!("failed to sweep stray upload sources: {:?}", e),
+            }
+        }
+    });
+
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(5));
+
+        loop {
+            interval.tick().await;
+
+            loop {
+                match process_next(&postgre).await {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(e) => {
+                        log::error!("failed to process image upload: {:?}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+async fn process_next(postgre: &PgPool) -> Result<bool, sqlx::Error> {
+    let mut tx = postgre.begin().await?;
+
+    let upload = sqlx::query!(
+        r"
+        SELECT id, structure
+        FROM structure_image_uploads
+        WHERE attempts < $1
+        ORDER BY id
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+        ",
+        MAX_ATTEMPTS
+    )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+    let upload = match upload {
+        Some(upload) => upload,
+        None => {
+            tx.commit().await?;
+            return Ok(false);
+        }
+    };
+
+    match store(upload.id, upload.structure, &mut tx).await {
+        Ok(()) => {
+            sqlx::query!(
+                r"DELETE FROM structure_image_uploads WHERE id = $1",
+                upload.id
+            )
+                .execute(&mut *tx)
+                .await?;
+
+            tx.commit().await?;
+
+            if let Err(e) = local::delete(&upload_key(upload.id)).await {
+                log::warn!("failed to delete processed upload {}: {:?}", upload.id, e);
+            }
+        }
+        Err(e) => {
+            // The transaction may be aborted, so record the failure on a fresh one.
+            tx.rollback().await?;
+
+            log::warn!("failed to process image upload {}: {}", upload.id, e);
+
+            let attempts = sqlx::query_scalar!(
+                r"UPDATE structure_image_uploads SET attempts = attempts + 1, error = $2 WHERE id = $1 RETURNING attempts",
+                upload.id,
+                e.to_string()
+            )
+                .fetch_one(postgre)
+                .await?;
+
+            // Nothing retries an upload past its last attempt, so drop it along with its source.
+            if attempts >= MAX_ATTEMPTS {
+                log::error!("giving up on image upload {} after {} attempts", upload.id, attempts);
+
+                sqlx::query!(
+                    r"DELETE FROM structure_image_uploads WHERE id = $1",
+                    upload.id
+                )
+                    .execute(postgre)
+                    .await?;
+
+                if let Err(e) = local::delete(&upload_key(upload.id)).await {
+                    log::warn!("failed to delete failed upload {}: {:?}", upload.id, e);
+                }
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+/// Deletes upload sources with no queued row, left behind when the transaction that queued them rolled back.
+async fn sweep(postgre: &PgPool) -> Result<usize, BlobError> {
+    let cutoff = SystemTime::now() - SWEEP_GRACE_PERIOD;
+
+    let sources = local::list("uploads/images")
+        .await?
+        .into_iter()
+        .filter(|(_, modified)| *modified < cutoff)
+        .filter_map(|(key, _)| key.rsplit('/').next().and_then(|id| id.parse::<i64>().ok()))
+        .collect::<Vec<_>>();
+
+    // Upload ids are never reused, so a source without a row now never gets one.
+    let queued = sqlx::query_scalar!(
+        r"SELECT id FROM structure_image_uploads WHERE id = ANY($1)",
+        &sources
+    )
+        .fetch_all(postgre)
+        .await?;
+
+    let mut swept = 0;
+    for upload in sources.into_iter().filter(|upload| !queued.contains(upload)) {
+        local::delete(&upload_key(upload)).await?;
+        swept += 1;
+    }
+
+    Ok(swept)
+}
+
+async fn store(upload: i64, structure: i64, tx: &mut sqlx::PgConnection) -> Result<(), ProcessError> {
+    let data = local::read(&upload_key(upload)).await?;
+
+    let processed = actix_rt::task::spawn_blocking(move || process(&data))
+        .await
+        .map_err(io::Error::other)??;
+
+    let mut stored = Vec::with_capacity(processed.variants.len());
+    for variant in &processed.variants {
+        stored.push(blobs::store(variant.data.clone(), &mut *tx).await?);
+    }
+
+    let full = processed.variants
+        .iter()
+        .zip(&stored)
+        .find(|(v, _)| v.size == "full" && v.format == "jpeg")
+        .map(|(_, blob)| blob)
+        .expect("full jpeg variant is always generated");
+
+    let image = sqlx::query_scalar!(
+        r"
+        INSERT INTO structure_images (structure, url, blob, width, height, blurhash, created)
+        VALUES ($1, $2, $3, $4, $5, $6, NOW())
+        RETURNING id
+        ",
+        structure,
+        full.url,
+        full.hash,
+        processed.width as i32,
+        processed.height as i32,
+        processed.blurhash
+    )
+        .fetch_one(&mut *tx)
+        .await?;
+
+    for (variant, blob) in processed.variants.iter().zip(&stored) {
+        sqlx::query!(
+            r"
+            INSERT INTO structure_image_variants (image, size, format, url, blob, width, height)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ",
+            image,
+            variant.size,
+            variant.format,
+            blob.url,
+            blob.hash,
+            variant.width as i32,
+            variant.height as i32
+        )
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn process(data: &[u8]) -> Result<Processed, ProcessError> {
+    let image = decode(data)?;
+
+    let small = image.thumbnail(32, 32).to_rgba8();
+    let blurhash = blurhash::encode(4, 3, small.width(), small.height(), small.as_raw())?;
+
+    let mut variants = Vec::with_capacity(SIZES.len() * 2);
+    for (size, edge) in SIZES {
+        let resized = if image.width() > edge || image.height() > edge {
+            image.resize(edge, edge, FilterType::Lanczos3)
+        } else {
+            image.clone()
+        };
+
+        variants.push(encode_jpeg(size, &resized)?);
+        variants.push(encode_webp(size, &resized));
+    }
+
+    Ok(Processed {
+        width: image.width(),
+        height: image.height(),
+        blurhash,
+        variants,
+    })
+}
+
+/// Center-crops an avatar to a square and encodes it at every avatar size.
+pub fn process_avatar(data: &[u8]) -> Result<Vec<Variant>, ImageError> {
+    let image = decode(data)?;
+
+    let edge = image.width().min(image.height());
+    let image = image.crop_imm((image.width() - edge) / 2, (image.height() - edge) / 2, edge, edge);
+
+    let mut variants = Vec::with_capacity(AVATAR_SIZES.len() * 2);
+    for (size, edge) in AVATAR_SIZES {
+        let resized = image.resize_exact(edge, edge, FilterType::Lanczos3);
+        variants.push(encode_jpeg(size, &resized)?);
+        variants.push(encode_webp(size, &resized));
+    }
+
+    Ok(variants)
+}
+
+fn decode(data: &[u8]) -> Result<DynamicImage, ImageError> {
+    let mut decoder = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation()?;
+
+    // Only the decoded pixels survive, so EXIF, GPS and other metadata are dropped here.
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+
+    Ok(image)
+}
+
+fn encode_jpeg(size: &'static str, image: &DynamicImage) -> Result<Variant, ImageError> {
+    let rgb = image.to_rgb8();
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY).encode_image(&rgb)?;
+
+    Ok(Variant {
+        size,
+        format: "jpeg",
+        width: rgb.width(),
+        height: rgb.height(),
+        data: jpeg,
+    })
+}
+
+/// Encodes lossy WebP through libwebp, as the `image` crate only writes lossless WebP.
+fn encode_webp(size: &'static str, image: &DynamicImage) -> Variant {
+    let rgba = image.to_rgba8();
+    let webp = webp::Encoder::from_rgba(rgba.as_raw(), rgba.width(), rgba.height()).encode(WEBP_QUALITY);
+
+    Variant {
+        size,
+        format: "webp",
+        width: rgba.width(),
+        height: rgba.height(),
+        data: webp.to_vec(),
+    }
+}
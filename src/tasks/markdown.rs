@@ -0,0 +1,117 @@
+use sqlx::PgPool;
+use crate::markdown;
+
+const BATCH_SIZE: i64 = 256;
+
+/// Renders descriptions, bios and comments stored before rendering happened on write.
+pub fn spawn(postgre: PgPool) {
+    actix_rt::spawn(async move {
+        match backfill(&postgre).await {
+            Ok(0) => {}
+            Ok(rendered) => log::info!("rendered {} markdown fields", rendered),
+            Err(e) => log::error!("failed to render markdown: {:?}", e),
+        }
+    });
+}
+
+async fn backfill(postgre: &PgPool) -> Result<usize, sqlx::Error> {
+    let mut rendered = 0;
+
+    loop {
+        let versions = sqlx::query!(
+            r"SELECT id, description FROM structure_versions WHERE description_html IS NULL LIMIT $1",
+            BATCH_SIZE
+        )
+            .fetch_all(postgre)
+            .await?;
+
+        if versions.is_empty() {
+            break;
+        }
+
+        for version in versions {
+            sqlx::query!(
+                r"UPDATE structure_versions SET description_html = $2 WHERE id = $1",
+                version.id,
+                markdown::render(&version.description)
+            )
+                .execute(postgre)
+                .await?;
+            rendered += 1;
+        }
+    }
+
+    loop {
+        let structures = sqlx::query!(
+            r"SELECT id, description FROM structures WHERE description_html IS NULL LIMIT $1",
+            BATCH_SIZE
+        )
+            .fetch_all(postgre)
+            .await?;
+
+        if structures.is_empty() {
+            break;
+        }
+
+        for structure in structures {
+            sqlx::query!(
+                r"UPDATE structures SET description_html = $2 WHERE id = $1",
+                structure.id,
+                markdown::render(&structure.description)
+            )
+                .execute(postgre)
+                .await?;
+            rendered += 1;
+        }
+    }
+
+    loop {
+        let users = sqlx::query!(
+            r#"SELECT id, bio AS "bio!" FROM users WHERE bio IS NOT NULL AND bio_html IS NULL LIMIT $1"#,
+            BATCH_SIZE
+        )
+            .fetch_all(postgre)
+            .await?;
+
+        if users.is_empty() {
+            break;
+        }
+
+        for user in users {
+            sqlx::query!(
+                r"UPDATE users SET bio_html = $2 WHERE id = $1",
+                user.id,
+                markdown::render(&user.bio)
+            )
+                .execute(postgre)
+                .await?;
+            rendered += 1;
+        }
+    }
+
+    loop {
+        let comments = sqlx::query!(
+            r"SELECT id, body FROM structure_comments WHERE body_html IS NULL LIMIT $1",
+            BATCH_SIZE
+        )
+            .fetch_all(postgre)
+            .await?;
+
+        if comments.is_empty() {
+            break;
+        }
+
+        for comment in comments {
+            sqlx::query!(
+                r"UPDATE structure_comments SET body_html = $2 WHERE id = $1",
+                comment.id,
+                markdown::render(&comment.body)
+            )
+                .execute(postgre)
+                .await?;
+            rendered += 1;
+        }
+    }
+
+    Ok(rendered)
+}
@@ -0,0 +1,8 @@
+pub mod publish;
+pub mod downloads;
+pub mod views;
+pub mod images;
+pub mod blobs;
+pub mod markdown;
+pub mod trending;
+pub mod badges;
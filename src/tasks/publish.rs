@@ -0,0 +1,31 @@
+use std::time::Duration;
+use sqlx::PgPool;
+
+/// Publishes structures whose scheduled publish time has passed.
+pub fn spawn(postgre: PgPool) {
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(60));
+
+        loop {
+            interval.tick().await;
+
+            let result = sqlx::query!(
+                r"
+                UPDATE structures
+                SET visibility = 'public', published = COALESCE(published, scheduled), scheduled = NULL
+                WHERE scheduled <= NOW()
+                "
+            )
+                .execute(&postgre)
+                .await;
+
+            match result {
+                Ok(result) if result.rows_affected() > 0 => {
+                    log::info!("published {} scheduled structures", result.rows_affected());
+                }
+                Ok(_) => {}
+                Err(e) => log::error!("failed to publish scheduled structures: {:?}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,71 @@
+use std::time::Duration;
+use sqlx::PgPool;
+use crate::models::structures::TrendingWindow;
+
+/// Rebuilds the trending scores of every window from recent activity.
+///
+/// Each view, download, comment and rating inside the window counts towards a structure's score,
+/// halving in weight every half window so that fresh activity outranks older bursts.
+/// Views are the cheapest signal, so they weigh a tenth of a download.
+pub fn spawn(postgre: PgPool) {
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(15 * 60));
+
+        loop {
+            interval.tick().await;
+
+            for window in TrendingWindow::ALL {
+                if let Err(e) = refresh(window, &postgre).await {
+                    log::error!("failed to refresh {} trending scores: {:?}", window.as_str(), e);
+                }
+            }
+        }
+    });
+}
+
+async fn refresh(window: TrendingWindow, postgre: &PgPool) -> Result<(), sqlx::Error> {
+    let mut tx = postgre.begin().await?;
+
+    sqlx::query!(
+        r"DELETE FROM structure_trending WHERE period = $1",
+        window.as_str()
+    )
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query!(
+        r"
+        INSERT INTO structure_trending (structure, period, score)
+        SELECT
+            a.structure,
+            $1,
+            SUM(a.weight * POWER(0.5, EXTRACT(EPOCH FROM NOW() - a.created)::DOUBLE PRECISION / (43200 * $2)))
+        FROM (
+            SELECT structure, day::TIMESTAMP AS created, downloads::DOUBLE PRECISION AS weight
+            FROM structure_downloads_daily
+            WHERE day >= (NOW() - MAKE_INTERVAL(days => $2))::DATE
+            UNION ALL
+            SELECT structure, day::TIMESTAMP, views::DOUBLE PRECISION / 10
+            FROM structure_views_daily
+            WHERE day >= (NOW() - MAKE_INTERVAL(days => $2))::DATE
+            UNION ALL
+            SELECT structure, created, 3.0
+            FROM structure_comments
+            WHERE removed IS NULL AND created >= NOW() - MAKE_INTERVAL(days => $2)
+            UNION ALL
+            SELECT structure, updated, 2.0
+            FROM structure_ratings
+            WHERE updated >= NOW() - MAKE_INTERVAL(days => $2)
+        ) a
+        JOIN structures s ON s.id = a.structure
+        WHERE s.visibility = 'public' AND s.published <= NOW()
+        GROUP BY a.structure
+        ",
+        window.as_str(),
+        window.days()
+    )
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await
+}